@@ -2,9 +2,10 @@ use game_core9::{
     any_message::AnyMessageBox,
     buttle_char::ButtleCharArgs,
     buttle_enemy::{ButtleEnemyArgs, EnemyInfomation},
+    enemy_action_pattern::EnemyActionPattern,
     core_actor::{GameCoreActor, UserInput},
     damage::DamageType,
-    enemy_skill::{EnemySkill, EnemySkillAction, EnemySkillTarget},
+    enemy_skill::{EnemySkill, EnemySkillAction, EnemySkillActionGroup, EnemySkillTarget},
     game_state::GameStateArgs,
     potential::Potential,
     skill::{SkillBox, SkillTrait},
@@ -18,14 +19,18 @@ fn enemy_skill1() -> EnemySkill {
         need_mp: 10.0,
         start_up_frames: 400,
         recovery_frame: 100,
-        actions: vec![(
-            EnemySkillTarget::Single,
-            EnemySkillAction::Damage {
-                ty: DamageType::Physics,
-                dmg_mag: 1.0,
-                count: 1,
-            },
+        actions: vec![EnemySkillActionGroup::new(
+            0,
+            vec![(
+                EnemySkillTarget::Single,
+                EnemySkillAction::Damage {
+                    ty: DamageType::Physics,
+                    dmg_mag: 1.0,
+                    count: 1,
+                },
+            )],
         )],
+        fallback: None,
     }
 }
 
@@ -37,6 +42,7 @@ fn enemy_skill2() -> EnemySkill {
         start_up_frames: 10,
         recovery_frame: 10,
         actions: vec![],
+        fallback: None,
     }
 }
 
@@ -48,6 +54,7 @@ fn enemy_skill3() -> EnemySkill {
         start_up_frames: 10,
         recovery_frame: 10,
         actions: vec![],
+        fallback: None,
     }
 }
 
@@ -91,6 +98,7 @@ fn elena() -> ButtleCharArgs {
             p_atk: 1.0,
             ty: game_core9::weapon::WeaponType::Cane,
         },
+        resources: vec![],
     }
 }
 
@@ -106,6 +114,7 @@ fn yuuko() -> ButtleCharArgs {
             p_atk: 1.0,
             ty: game_core9::weapon::WeaponType::Cane,
         },
+        resources: vec![],
     }
 }
 
@@ -121,6 +130,7 @@ fn asya() -> ButtleCharArgs {
             p_atk: 1.0,
             ty: game_core9::weapon::WeaponType::Cane,
         },
+        resources: vec![],
     }
 }
 
@@ -136,15 +146,24 @@ fn nowaru() -> ButtleCharArgs {
             p_atk: 1.0,
             ty: game_core9::weapon::WeaponType::Cane,
         },
+        resources: vec![],
     }
 }
 
 fn args() -> GameStateArgs {
     GameStateArgs {
         chars: vec![elena(), yuuko(), asya(), nowaru()],
+        seed: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64,
         enemy: ButtleEnemyArgs {
-            action_patterns: vec![vec![1, 2, 3]],
+            action_patterns: vec![EnemyActionPattern::new(vec![1, 2, 3])],
             default_passive: vec![],
+            phases: vec![],
+            stagger: None,
+            hate_decay_per_sec: 0.0,
+            resistances: vec![],
             info: EnemyInfomation {
                 id: 1,
                 desctiption: "",
@@ -4,9 +4,11 @@ use game_core9::{
     buttle_char::{ButtleChar, CharCondition},
     buttle_enemy::ButtleEnemy,
     buttle_skill::ButtleSkill,
-    core_actor::{GameCoreActor, GameCoreOutput, UserInput},
+    core_actor::{GameCoreActor, GameCoreOutput, OutputEvent, UserInput},
+    enemy_skill::ForecastTarget,
     lt_common::LtCommon,
-    runtime_id::RuntimeSkillId,
+    passive::{PassiveCategory, PassiveView},
+    runtime_id::{LtId, RuntimeSkillId},
 };
 use iced::{
     Background, Border, Color, Element, Event, Length, Padding,
@@ -72,6 +74,7 @@ pub struct GamePage {
     speed: GameSpeed,
     core: GameCoreActor,
     use_skill_queue: VecDeque<RuntimeSkillId>,
+    enemy_phase_name: Option<&'static str>,
 }
 
 impl GamePage {
@@ -81,6 +84,7 @@ impl GamePage {
             paused: false,
             core: game_core(),
             use_skill_queue: VecDeque::new(),
+            enemy_phase_name: None,
         }
     }
 
@@ -94,8 +98,14 @@ impl GamePage {
         self.core.tick(user_input, &mut output_buffer).unwrap();
 
         for output in output_buffer {
-            if let GameCoreOutput::Event(_) = output {
-                return Some(Box::new(HomePage));
+            match output {
+                GameCoreOutput::Event(OutputEvent::Win | OutputEvent::Lose) => {
+                    return Some(Box::new(HomePage));
+                }
+                GameCoreOutput::Event(OutputEvent::EnemyPhaseChanged { name, .. }) => {
+                    self.enemy_phase_name = Some(name);
+                }
+                _ => {}
             }
         }
 
@@ -182,10 +192,42 @@ impl PageTrait for GamePage {
 //--------------------------------------------------//
 impl GamePage {
     fn enemy_side_view(&self) -> Column<'_, MainAppMessage> {
-        column![row![self.enemy_item_view(self.core.state().get_enemy())].spacing(10)]
+        let mut col = Column::new();
+
+        if let Some(name) = self.enemy_phase_name {
+            col = col.push(text(name).size(20));
+        }
+
+        col.push(row![self.enemy_item_view(self.core.state().get_enemy())].spacing(10))
+            .push(self.enemy_forecast_view())
             .align_x(Horizontal::Center)
     }
 
+    fn enemy_forecast_view(&self) -> Column<'_, MainAppMessage> {
+        let state = self.core.state();
+        let mut col = Column::new().push(text("行動予測").size(16));
+
+        for forecast in state.get_enemy().forecast(state) {
+            let mut line = forecast.skill.name.to_string();
+            for dmg in forecast.damages.iter() {
+                let target_name = match dmg.target {
+                    ForecastTarget::Lt(LtId::Char(id)) => Cow::Borrowed(state.get_char(id).name()),
+                    ForecastTarget::Lt(LtId::Enemy) => Cow::Borrowed(state.get_enemy().info().name),
+                    ForecastTarget::Random(n) => Cow::Owned(format!("ランダム{}人", n)),
+                };
+                line.push_str(&format!(
+                    " / {}: {}{}",
+                    target_name,
+                    dmg.dmg.round(),
+                    dmg.ty.type_str()
+                ));
+            }
+            col = col.push(text(line).size(14));
+        }
+
+        col.spacing(3)
+    }
+
     fn player_side_view(&self) -> Row<'_, MainAppMessage> {
         row![
             self.auther_panel(),
@@ -310,6 +352,7 @@ impl GamePage {
                             )
                             .width(Length::Fill)
                             .height(Length::Fixed(11.0)),
+                            self.resource_bars(lt.lt_common()),
                             column![row![tooltip(
                                 button(text!("ステータス詳細").size(14)).padding(3),
                                 container(self.lt_status_view(lt.lt_common()))
@@ -347,6 +390,21 @@ impl GamePage {
         .spacing(10)
     }
 
+    /// HP/MP以外のリソース
+    fn resource_bars(&self, lt_common: &LtCommon) -> Column<'static, MainAppMessage> {
+        Column::with_children(lt_common.resources().map(|(res, value)| {
+            column![
+                text!("{}: {}/{}", res.name, value.round(), res.max.round()),
+                bar(value, res.max, Color::from_rgb8(0xFF, 0x8C, 0x00))
+                    .width(Length::Fill)
+                    .height(Length::Fixed(11.0)),
+            ]
+            .spacing(5)
+            .into()
+        }))
+        .spacing(5)
+    }
+
     fn passive_list(&self, lt: Lt<'_>) -> Container<'_, MainAppMessage> {
         // https://github.com/iced-rs/iced/discussions/2013
         // ここで議論されているようなUIにしたいけどやり方がわからん
@@ -354,8 +412,16 @@ impl GamePage {
 
         let mut col = Column::new();
 
-        for passive_text in lt.lt_common().passive.display() {
-            col = col.push(text(passive_text));
+        // バフ、デバフ、その他の順に並べる
+        let mut views = lt.lt_common().passive.views().collect::<Vec<_>>();
+        views.sort_by_key(|v| match v.category {
+            PassiveCategory::Buff => 0,
+            PassiveCategory::Debuff => 1,
+            PassiveCategory::Neutral => 2,
+        });
+
+        for view in views {
+            col = col.push(text(passive_view_text(&view)));
         }
 
         Container::new(scrollable(col.width(Length::Fill)).width(Length::Fill))
@@ -507,7 +573,12 @@ impl GamePage {
                 .spacing(5),
             ]
             .spacing(10),
-            Row::with_children(lt_common.passive.display().map(|d| text(d).into()))
+            Row::with_children(
+                lt_common
+                    .passive
+                    .views()
+                    .map(|v| text(passive_view_text(&v)).into())
+            )
                 .spacing(5)
                 .padding(5)
         ]
@@ -588,20 +659,46 @@ impl<'a> Lt<'a> {
     }
 
     fn condition(&self) -> String {
-        let s = match self {
-            Lt::Char(char) => match char.current_condition() {
-                CharCondition::UseSkill(s) => match s.kind {
-                    game_core9::skill::CharSkillProgressKind::Chanting => "詠唱中",
-                    game_core9::skill::CharSkillProgressKind::Acting => "行動中",
-                },
-                CharCondition::Wait => "待機中",
-            },
-            Lt::Enemy(buttle_enemy) => match buttle_enemy.current_condition().ty {
-                game_core9::buttle_enemy::EnemyConditionType::StartUp => "準備中",
-                game_core9::buttle_enemy::EnemyConditionType::Recovery => "硬直中",
-            },
-        };
+        match self {
+            Lt::Char(char) => {
+                let s = match char.current_condition() {
+                    CharCondition::UseSkill(s) => match s.kind {
+                        game_core9::skill::CharSkillProgressKind::Chanting => "詠唱中",
+                        game_core9::skill::CharSkillProgressKind::Acting => "行動中",
+                    },
+                    CharCondition::Wait => "待機中",
+                };
+                s.to_string()
+            }
+            Lt::Enemy(buttle_enemy) => {
+                let condition = buttle_enemy.current_condition();
+                let ty = match condition.ty {
+                    game_core9::buttle_enemy::EnemyConditionType::StartUp => "準備中",
+                    game_core9::buttle_enemy::EnemyConditionType::Acting => "行動中",
+                    game_core9::buttle_enemy::EnemyConditionType::Recovery => "硬直中",
+                    game_core9::buttle_enemy::EnemyConditionType::Broken => "ブレイク中",
+                };
+                format!(
+                    "{} {} ({}%)",
+                    condition.skill.name,
+                    ty,
+                    (condition.progress.progress() * 100.0).round()
+                )
+            }
+        }
+    }
+}
 
-        s.to_string()
+fn passive_view_text(view: &PassiveView) -> String {
+    let mut passive_text = view.name.to_string();
+    if !view.summary.is_empty() {
+        passive_text = format!("{passive_text} ({})", view.summary);
+    }
+    if view.stacks > 1 {
+        passive_text = format!("{passive_text} x{}", view.stacks);
+    }
+    if let Some(frames) = view.remaining_frames {
+        passive_text = format!("{passive_text} 残り{:.1}秒", frames as f32 / game_core9::FPS);
     }
+    passive_text
 }
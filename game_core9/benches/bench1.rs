@@ -7,6 +7,7 @@ mod a {
         buttle_char::ButtleCharArgs,
         buttle_enemy::{ButtleEnemyArgs, EnemyInfomation},
         core_actor::{EffectsBuffer, GameCoreActor, UserInput},
+        enemy_action_pattern::EnemyActionPattern,
         enemy_skill::EnemySkill,
         game_state::GameStateArgs,
         potential::Potential,
@@ -22,6 +23,7 @@ mod a {
             start_up_frames: 10,
             recovery_frame: 10,
             actions: vec![],
+            fallback: None,
         }
     }
 
@@ -33,6 +35,7 @@ mod a {
             start_up_frames: 10,
             recovery_frame: 10,
             actions: vec![],
+            fallback: None,
         }
     }
 
@@ -44,6 +47,7 @@ mod a {
             start_up_frames: 10,
             recovery_frame: 10,
             actions: vec![],
+            fallback: None,
         }
     }
 
@@ -88,14 +92,20 @@ mod a {
                 p_atk: 1.0,
                 ty: game_core9::weapon::WeaponType::Cane,
             },
+            resources: vec![],
         }
     }
     fn args() -> GameStateArgs {
         GameStateArgs {
             chars: vec![char1()],
+            seed: 0,
             enemy: ButtleEnemyArgs {
-                action_patterns: vec![vec![1, 2, 3]],
+                action_patterns: vec![EnemyActionPattern::new(vec![1, 2, 3])],
                 default_passive: vec![],
+                phases: vec![],
+                stagger: None,
+                hate_decay_per_sec: 0.0,
+                resistances: vec![],
                 info: EnemyInfomation {
                     desctiption: "",
                     name: "",
@@ -1,13 +1,14 @@
 use crate::{
-    LevelNum, StaticCharId, StatusNum, TimeNum,
+    FPS, LevelNum, StaticCharId, StatusNum, TimeNum,
     buttle_skill::ButtleSkill,
     core_actor::EffectsBuffer,
     effect::Effect,
     game_state::GameState,
     lt_common::LtCommon,
     potential::Potential,
+    resource::ResourceBar,
     runtime_id::{LtId, RuntimeCharId, RuntimeSkillId},
-    skill::{CharSkillProgress, SkillBox},
+    skill::{CharSkillProgress, CharSkillProgressKind, SkillBox},
     weapon::{Weapon, WeaponType},
 };
 
@@ -16,6 +17,8 @@ pub struct ButtleCharArgs {
     pub potential: Potential,
     pub skills: Vec<SkillBox>,
     pub weapon: Weapon,
+    /// 武器が持つリソースに加えて持つリソース
+    pub resources: Vec<ResourceBar>,
     pub level: LevelNum,
     pub name: &'static str,
 }
@@ -34,7 +37,17 @@ pub struct ButtleChar {
 
 impl ButtleChar {
     pub fn new(runtime_id: RuntimeCharId, args: ButtleCharArgs) -> Result<Self, crate::Error> {
-        let lt_common = LtCommon::new_with_weapon(args.potential, args.level, args.weapon.clone());
+        let mut lt_common =
+            LtCommon::new_with_weapon(args.potential, args.level, args.weapon.clone());
+        for bar in args
+            .weapon
+            .ty
+            .resource_bars()
+            .into_iter()
+            .chain(args.resources)
+        {
+            lt_common.add_resource_bar(bar)?;
+        }
 
         let mut skills = Vec::with_capacity(args.skills.len());
         for (i, s) in args.skills.into_iter().enumerate() {
@@ -85,16 +98,31 @@ impl ButtleChar {
     }
 
     pub(crate) fn tick(&self, state: &GameState, effects_buffer: &mut EffectsBuffer) {
-        if let Some(skill_id) = self.current_using_skill {
+        // スタン中はスキルの進行とクールダウンの回復が止まる
+        let stunned = self.lt_common.is_stunned();
+
+        if let Some(skill_id) = self.current_using_skill
+            && !stunned
+        {
             let skill = self.skills.get(skill_id.idx as usize).unwrap();
             skill.skill_box().tick(skill_id, state, effects_buffer);
         }
 
         self.lt_common.tick(self.lt_id(), state, effects_buffer);
-        effects_buffer.push(Effect::HealSkillCooldownAll {
-            target_id: self.runtime_id,
-            num: 0.01, // 1 = 1s
-        });
+        if !stunned {
+            effects_buffer.push(Effect::HealSkillCooldownAll {
+                target_id: self.runtime_id,
+                num: self.lt_common.cooldown_heal_mag() / FPS, // 1 = 1s
+            });
+        }
+
+        let hate_decay = state.get_enemy().hate_decay_mag_per_frame();
+        if hate_decay < 1.0 && self.hate > 0.0 {
+            effects_buffer.push(Effect::MulHate {
+                target_id: self.runtime_id,
+                mag: hate_decay,
+            });
+        }
     }
 
     pub fn get_skill(&self, id: RuntimeSkillId) -> &ButtleSkill {
@@ -116,10 +144,26 @@ impl ButtleChar {
         });
     }
 
+    /// 獲得ヘイトの倍率がかかる
     pub(crate) fn add_hate(&mut self, num: StatusNum) {
+        self.hate += num * self.lt_common.hate_mag();
+    }
+
+    /// 獲得ヘイトの倍率がかからない
+    pub(crate) fn add_hate_raw(&mut self, num: StatusNum) {
         self.hate += num;
     }
 
+    /// ヘイトは0未満にならない
+    pub(crate) fn reduce_hate(&mut self, num: StatusNum) {
+        self.hate = (self.hate - num).max(0.0);
+    }
+
+    pub(crate) fn mul_hate(&mut self, mag: StatusNum) {
+        assert!(mag >= 0.0);
+        self.hate *= mag;
+    }
+
     pub fn hate(&self) -> StatusNum {
         self.hate
     }
@@ -135,6 +179,21 @@ impl ButtleChar {
         self.name
     }
 
+    pub fn current_using_skill(&self) -> Option<RuntimeSkillId> {
+        self.current_using_skill
+    }
+
+    /// スキルを詠唱中かどうか
+    pub fn is_chanting(&self) -> bool {
+        matches!(
+            self.current_condition(),
+            CharCondition::UseSkill(CharSkillProgress {
+                kind: CharSkillProgressKind::Chanting,
+                ..
+            })
+        )
+    }
+
     pub fn current_condition(&self) -> CharCondition {
         let skill_progress = self.current_using_skill.map(|s| {
             self.get_skill(s)
@@ -1,10 +1,18 @@
 use std::fmt::Debug;
 
 use crate::{
-    LevelNum, StaticEnemyId, StaticEnemySkillId,
-    buttle_enemy::enemy_skill_runner::EnemySkillRunnner, core_actor::EffectsBuffer,
-    enemy_skill::EnemySkill, game_state::GameState, lt_common::LtCommon, passive::PassiveBox,
-    potential::Potential, progress_state::ProgressState, runtime_id::LtId,
+    FPS, LevelNum, StaticEnemyId, StaticPassiveId, StatusNum, TimeNum,
+    buttle_enemy::enemy_skill_runner::{EnemySkillRunnner, RunnerActionPattern},
+    core_actor::EffectsBuffer,
+    effect::Effect,
+    enemy_action_pattern::EnemyActionPattern,
+    enemy_skill::{EnemySkill, ForecastDamage},
+    game_state::GameState,
+    lt_common::LtCommon,
+    passive::{PassiveBox, PassiveTag},
+    potential::Potential,
+    progress_state::ProgressState,
+    runtime_id::LtId,
 };
 
 mod enemy_skill_runner;
@@ -21,18 +29,80 @@ pub struct ButtleEnemyArgs {
     pub info: EnemyInfomation,
     pub potential: Potential,
     pub skills: Vec<EnemySkill>,
-    pub action_patterns: Vec<Vec<StaticEnemySkillId>>,
+    pub action_patterns: Vec<EnemyActionPattern>,
     pub default_passive: Vec<PassiveBox>,
+    /// hp_thresholdの降順で並んでいる必要がある
+    pub phases: Vec<EnemyPhase>,
+    /// Noneの場合はブレイクしない
+    pub stagger: Option<EnemyStagger>,
+    /// 1秒あたりに減少するキャラクターのヘイトの割合 (0.0 ~ 1.0)
+    pub hate_decay_per_sec: StatusNum,
+    /// タグごとのパッシブへの耐性 (0.0 ~ 1.0)。1.0の場合は無効
+    pub resistances: Vec<(PassiveTag, StatusNum)>,
 }
 
+#[derive(Debug, Clone)]
+pub struct EnemyStagger {
+    /// ブレイクゲージの最大値。キャラクターから受けたダメージの分だけ溜まる
+    pub max: StatusNum,
+    /// ブレイク状態が続くフレーム数
+    pub broken_frames: u64,
+    /// ブレイク中の被ダメージ倍率
+    pub broken_recv_dmg_mag: StatusNum,
+}
+
+/// HPが閾値を下回った時に移行するフェーズ
+#[derive(Debug, Clone)]
+pub struct EnemyPhase {
+    pub name: &'static str,
+    /// HPの割合がこの値を下回ったらこのフェーズに移行する (0.0 ~ 1.0)
+    pub hp_threshold: StatusNum,
+    /// Noneの場合はアクションパターンを変更しない
+    pub action_patterns: Option<Vec<EnemyActionPattern>>,
+    pub add_passives: Vec<PassiveBox>,
+    pub remove_passives: Vec<StaticPassiveId>,
+    /// trueの場合、現在実行中のスキル以外のスケジュールを破棄して組み直す
+    pub flush_schedule: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EnemyConditionType {
+    /// 最初の行動までの準備
     StartUp,
+    /// 最初の行動から最後の行動まで
+    Acting,
+    /// 最後の行動の後の硬直
     Recovery,
+    /// ブレイク中。skillはブレイク明けに使用するスキル
+    Broken,
 }
 
-pub struct EnemyCondition {
+#[derive(Debug, Clone)]
+pub struct EnemyCondition<'a> {
     pub ty: EnemyConditionType,
+    /// 全体フレームに対しての進捗ではないことに注意
+    /// tyがStartUpなら"準備"の進捗、Actingなら最初の行動から最後の行動までの進捗
     pub progress: ProgressState,
+    /// 実行中のスキル
+    pub skill: &'a EnemySkill,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnemySkillForecast<'a> {
+    pub skill: &'a EnemySkill,
+    pub damages: Vec<ForecastDamage>,
+}
+
+#[derive(Debug)]
+struct ButtlePhase {
+    name: &'static str,
+    hp_threshold: StatusNum,
+    /// フェーズ移行時にtakeされる
+    action_patterns: Option<Vec<RunnerActionPattern>>,
+    /// フェーズ移行時にtakeされる
+    add_passives: Vec<PassiveBox>,
+    remove_passives: Vec<StaticPassiveId>,
+    flush_schedule: bool,
 }
 
 #[derive(Debug)]
@@ -40,51 +110,361 @@ pub struct ButtleEnemy {
     lt_common: LtCommon,
     skill_runner: EnemySkillRunnner,
     info: EnemyInfomation,
+    phases: Vec<ButtlePhase>,
+    /// 0は初期状態。Nの場合はphases[N - 1]のフェーズ
+    current_phase: usize,
+    stagger: Option<EnemyStagger>,
+    stagger_gauge: StatusNum,
+    /// 0の場合はブレイクしていない
+    broken_frames_left: u64,
+    hate_decay_per_sec: StatusNum,
+    /// 1以上になるとスキルが1フレーム進む
+    frame_progress: TimeNum,
+}
+
+fn resolve_action_patterns(
+    skills: &[EnemySkill],
+    patterns: Vec<EnemyActionPattern>,
+) -> Result<Vec<RunnerActionPattern>, crate::Error> {
+    // 条件を満たすパターンが無くなることがないように、条件なしのパターンを必須にする
+    if !patterns
+        .iter()
+        .any(|p| p.weight > 0 && p.conditions.is_empty())
+    {
+        return Err(crate::Error::InvalidArgument(
+            "At least one unconditional action pattern with a positive weight is required"
+                .to_string(),
+        ));
+    }
+
+    let mut action_patterns = Vec::with_capacity(patterns.len());
+    for pattern in patterns {
+        if pattern.skills.is_empty() {
+            return Err(crate::Error::InvalidArgument(
+                "Action pattern must not be empty".to_string(),
+            ));
+        }
+
+        let mut skill_idxs = Vec::with_capacity(pattern.skills.len());
+        for skill_id in pattern.skills {
+            let Some(idx) = skills.iter().position(|s| s.id == skill_id) else {
+                return Err(crate::Error::NotFoundSkillInActionPattern);
+            };
+            skill_idxs.push(idx);
+        }
+
+        action_patterns.push(RunnerActionPattern {
+            skill_idxs,
+            weight: pattern.weight,
+            conditions: pattern.conditions,
+        });
+    }
+
+    Ok(action_patterns)
 }
 
 impl ButtleEnemy {
     pub(crate) fn new(args: ButtleEnemyArgs) -> Result<Self, crate::Error> {
-        let mut action_patterns = Vec::with_capacity(args.action_patterns.len());
-        for pattern in args.action_patterns {
-            action_patterns.push(Vec::with_capacity(pattern.len()));
-            let last = action_patterns.last_mut().unwrap();
-            for skill_id in pattern {
-                let Some(idx) = args.skills.iter().position(|s| s.id == skill_id) else {
-                    return Err(crate::Error::NotFoundSkillInActionPattern);
-                };
-                last.push(idx);
+        let action_patterns = resolve_action_patterns(&args.skills, args.action_patterns)?;
+
+        let mut phases = Vec::with_capacity(args.phases.len());
+        let mut prev_threshold = 1.0;
+        for phase in args.phases {
+            if !(phase.hp_threshold > 0.0 && phase.hp_threshold <= prev_threshold) {
+                return Err(crate::Error::InvalidArgument(
+                    "Phase hp_threshold must be in (0, 1] and in descending order".to_string(),
+                ));
             }
+            prev_threshold = phase.hp_threshold;
+
+            let action_patterns = match phase.action_patterns {
+                Some(patterns) => Some(resolve_action_patterns(&args.skills, patterns)?),
+                None => None,
+            };
+
+            phases.push(ButtlePhase {
+                name: phase.name,
+                hp_threshold: phase.hp_threshold,
+                action_patterns,
+                add_passives: phase.add_passives,
+                remove_passives: phase.remove_passives,
+                flush_schedule: phase.flush_schedule,
+            });
+        }
+
+        if !args
+            .skills
+            .iter()
+            .all(|s| s.actions.is_sorted_by_key(|g| g.frame))
+        {
+            return Err(crate::Error::InvalidArgument(
+                "Enemy skill actions must be sorted by frame".to_string(),
+            ));
+        }
+        // 準備フレームは最初の行動が発動するまでなので、最初の行動は相対フレーム0にする
+        if args
+            .skills
+            .iter()
+            .any(|s| s.actions.first().is_some_and(|g| g.frame != 0))
+        {
+            return Err(crate::Error::InvalidArgument(
+                "The first enemy skill action group must be at frame 0".to_string(),
+            ));
+        }
+
+        let mut fallbacks = Vec::with_capacity(args.skills.len());
+        for skill in args.skills.iter() {
+            let fallback = match skill.fallback {
+                Some(skill_id) => {
+                    let Some(idx) = args.skills.iter().position(|s| s.id == skill_id) else {
+                        return Err(crate::Error::NotFoundFallbackSkill);
+                    };
+                    Some(idx)
+                }
+                None => None,
+            };
+            fallbacks.push(fallback);
+        }
+
+        if let Some(stagger) = &args.stagger
+            && !(stagger.max > 0.0
+                && stagger.broken_frames > 0
+                && stagger.broken_recv_dmg_mag >= 0.0)
+        {
+            return Err(crate::Error::InvalidArgument(
+                "Invalid enemy stagger".to_string(),
+            ));
+        }
+
+        if !(0.0..=1.0).contains(&args.hate_decay_per_sec) {
+            return Err(crate::Error::InvalidArgument(
+                "hate_decay_per_sec must be in [0, 1]".to_string(),
+            ));
+        }
+
+        if args
+            .resistances
+            .iter()
+            .any(|(_, chance)| !(0.0..=1.0).contains(chance))
+        {
+            return Err(crate::Error::InvalidArgument(
+                "resistance must be in [0, 1]".to_string(),
+            ));
         }
 
         let mut lt_common = LtCommon::new(args.potential, args.level);
+        args.resistances.into_iter().for_each(|(tag, chance)| {
+            lt_common.set_resistance(tag, chance);
+        });
         args.default_passive.into_iter().for_each(|p| {
-            lt_common.passive.add(p);
+            lt_common.passive.add(p, 0);
         });
 
+        let skill_runner = EnemySkillRunnner::new(args.skills, fallbacks, action_patterns);
+
         Ok(ButtleEnemy {
             lt_common,
-            skill_runner: EnemySkillRunnner::new(args.skills, action_patterns),
+            skill_runner,
             info: args.info,
+            phases,
+            current_phase: 0,
+            stagger: args.stagger,
+            stagger_gauge: 0.0,
+            broken_frames_left: 0,
+            hate_decay_per_sec: args.hate_decay_per_sec,
+            frame_progress: 0.0,
         })
     }
 
     pub(crate) fn tick(&self, state: &GameState, effects_buffer: &mut EffectsBuffer) {
         self.lt_common.tick(self.lt_id(), state, effects_buffer);
-        self.skill_runner.tick(state, effects_buffer);
+
+        let phase = self.reached_phase();
+        if phase > self.current_phase {
+            effects_buffer.push(Effect::EnemyChangePhase { phase });
+        }
+
+        if self.is_broken() {
+            effects_buffer.push(Effect::EnemyDecrementBrokenFrame);
+            return;
+        }
+
+        if self
+            .stagger
+            .as_ref()
+            .is_some_and(|s| self.stagger_gauge >= s.max)
+        {
+            // ブレイクするフレームではスキルを進めない
+            effects_buffer.push(Effect::EnemyBreak);
+            return;
+        }
+
+        if self.lt_common.is_stunned() {
+            return;
+        }
+
+        // 鈍足の場合は数フレームに1回しかスキルが進まない
+        let slow_mag = self.lt_common.slow_mag();
+        effects_buffer.push(Effect::EnemyAddFrameProgress { num: slow_mag });
+        if self.frame_progress + slow_mag >= 1.0 {
+            self.skill_runner.tick(state, effects_buffer);
+        }
     }
 
-    pub(crate) fn skill_runner_increment_frame(&mut self) {
-        self.skill_runner.increment_frame();
+    pub(crate) fn add_frame_progress(&mut self, num: TimeNum) {
+        self.frame_progress += num;
     }
 
-    pub fn current_condition(&self) -> EnemyCondition {
-        self.skill_runner.current_condition()
+    /// キャラクターから受けたダメージでブレイクゲージを溜める
+    pub(crate) fn add_stagger(&mut self, dmg: StatusNum) {
+        if self.stagger.is_none() || self.is_broken() {
+            return;
+        }
+        self.stagger_gauge += dmg;
+    }
+
+    /// 現在のスキルを中断してブレイク状態になる
+    /// 呼び出し元はskill_runner_fill_scheduleを呼ぶ必要がある
+    pub(crate) fn start_broken(&mut self) {
+        let stagger = self
+            .stagger
+            .as_ref()
+            .expect("ブレイクしない敵はブレイクさせない");
+        self.broken_frames_left = stagger.broken_frames;
+        self.stagger_gauge = 0.0;
+        self.skill_runner.cancel_current_skill();
+    }
+
+    pub(crate) fn decrement_broken_frame(&mut self) {
+        self.broken_frames_left = self.broken_frames_left.saturating_sub(1);
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.broken_frames_left > 0
+    }
+
+    /// ブレイク中の被ダメージ倍率。ブレイクしていない場合は1.0
+    pub fn broken_recv_dmg_mag(&self) -> StatusNum {
+        match &self.stagger {
+            Some(stagger) if self.is_broken() => stagger.broken_recv_dmg_mag,
+            _ => 1.0,
+        }
+    }
+
+    /// 1フレームごとにキャラクターのヘイトに乗算される値
+    pub fn hate_decay_mag_per_frame(&self) -> StatusNum {
+        (1.0 - self.hate_decay_per_sec).powf(1.0 / FPS)
+    }
+
+    /// ブレイクゲージの溜まり具合。ブレイクしない敵の場合はNone
+    pub fn stagger_gauge(&self) -> Option<ProgressState> {
+        let stagger = self.stagger.as_ref()?;
+        ProgressState::new(self.stagger_gauge.min(stagger.max), stagger.max)
+    }
+
+    /// 現在のHPで到達しているフェーズ
+    fn reached_phase(&self) -> usize {
+        let hp_per = self.lt_common.hp_per();
+        self.phases
+            .iter()
+            .rposition(|p| hp_per < p.hp_threshold)
+            .map(|idx| idx + 1)
+            .unwrap_or(0)
+    }
+
+    /// phaseまでのフェーズを順番に適用する
+    /// スケジュールを組み直す必要がある場合trueを返す
+    pub(crate) fn change_phase(&mut self, phase: usize, frame: u64) -> bool {
+        assert!(phase <= self.phases.len());
+
+        let mut need_flush = false;
+        while self.current_phase < phase {
+            self.current_phase += 1;
+            let next = &mut self.phases[self.current_phase - 1];
+
+            for id in next.remove_passives.iter() {
+                self.lt_common.passive.remove(*id);
+            }
+            for passive in std::mem::take(&mut next.add_passives) {
+                self.lt_common.passive.add(passive, frame);
+            }
+            if let Some(patterns) = next.action_patterns.take() {
+                self.skill_runner.set_action_patterns(patterns);
+            }
+            need_flush |= next.flush_schedule;
+        }
+
+        need_flush
+    }
+
+    /// 0は初期状態。Nの場合はButtleEnemyArgs::phases[N - 1]のフェーズ
+    pub fn current_phase(&self) -> usize {
+        self.current_phase
+    }
+
+    /// 初期状態の場合はNone
+    pub fn current_phase_name(&self) -> Option<&'static str> {
+        self.current_phase
+            .checked_sub(1)
+            .map(|idx| self.phases[idx].name)
+    }
+
+    /// 現在のスキルが終了した場合trueを返す
+    /// trueを返した場合、呼び出し元はskill_runner_fill_scheduleを呼ぶ必要がある
+    pub(crate) fn skill_runner_increment_frame(&mut self) -> bool {
+        self.frame_progress = (self.frame_progress - 1.0).max(0.0);
+        self.skill_runner.increment_frame()
+    }
+
+    pub(crate) fn selectable_action_patterns(&self, state: &GameState) -> Vec<usize> {
+        self.skill_runner.selectable_action_patterns(state)
+    }
+
+    pub(crate) fn skill_runner_fill_schedule(&mut self, selectable: &[usize]) {
+        self.skill_runner
+            .fill_schedule(selectable, self.lt_common.mp());
+    }
+
+    pub(crate) fn skill_runner_fizzle(&mut self) {
+        self.skill_runner.fizzle();
+    }
+
+    pub(crate) fn skill_runner_flush_schedule(&mut self, selectable: &[usize]) {
+        self.skill_runner.flush_schedule(selectable);
+    }
+
+    pub fn current_condition(&self) -> EnemyCondition<'_> {
+        let condition = self.skill_runner.current_condition();
+
+        match &self.stagger {
+            Some(stagger) if self.is_broken() => EnemyCondition {
+                ty: EnemyConditionType::Broken,
+                progress: ProgressState::new(
+                    (stagger.broken_frames - self.broken_frames_left) as f32,
+                    stagger.broken_frames as f32,
+                )
+                .unwrap(),
+                skill: condition.skill,
+            },
+            _ => condition,
+        }
     }
 
     pub fn view_skills(&self) -> impl Iterator<Item = &EnemySkill> {
         self.skill_runner.view_skills()
     }
 
+    /// view_skillsの各スキルについて、現在の状態で使用した場合のダメージを見積もる
+    pub fn forecast<'a>(
+        &'a self,
+        state: &'a GameState,
+    ) -> impl Iterator<Item = EnemySkillForecast<'a>> {
+        self.view_skills().map(|skill| EnemySkillForecast {
+            skill,
+            damages: skill.forecast_damages(state),
+        })
+    }
+
     pub fn lt_id(&self) -> LtId {
         LtId::Enemy
     }
@@ -3,63 +3,188 @@ use std::collections::VecDeque;
 use rand::{Rng, rng};
 
 use crate::{
+    StatusNum,
     buttle_enemy::{EnemyCondition, EnemyConditionType},
     core_actor::EffectsBuffer,
     effect::Effect,
+    enemy_action_pattern::EnemyActionCondition,
     enemy_skill::EnemySkill,
     game_state::GameState,
     progress_state::ProgressState,
+    runtime_id::LtId,
 };
 
+#[derive(Debug)]
+pub(crate) struct RunnerActionPattern {
+    /// EnemySkillRunnner::skillsのindex
+    pub skill_idxs: Vec<usize>,
+    pub weight: u32,
+    pub conditions: Vec<EnemyActionCondition>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ScheduledSkill {
+    /// EnemySkillRunnner::skillsのindex
+    skill_idx: usize,
+    /// パターンの最初のスキルのみ持つ。開始時に条件を確認し直すアクションパターンのindex
+    check_pattern: Option<usize>,
+}
+
 #[derive(Debug)]
 pub struct EnemySkillRunnner {
-    schedule: VecDeque<usize>,
+    schedule: VecDeque<ScheduledSkill>,
     current_frame: u64,
+    /// 現在のスキルがMP不足で不発になった
+    fizzled: bool,
     skills: Vec<EnemySkill>,
-    action_patterns: Vec<Vec<usize>>,
+    /// skillsと同じ並び。MPが足りない時に代わりに使うスキルのindex
+    fallbacks: Vec<Option<usize>>,
+    action_patterns: Vec<RunnerActionPattern>,
 }
 impl EnemySkillRunnner {
     pub const NUM_VIEW_SKILLS: usize = 5;
-    pub fn new(skills: Vec<EnemySkill>, action_patterns: Vec<Vec<usize>>) -> Self {
+
+    /// スケジュールは空の状態で作られる
+    /// 使用する前にfill_scheduleを呼ぶ必要がある
+    pub(crate) fn new(
+        skills: Vec<EnemySkill>,
+        fallbacks: Vec<Option<usize>>,
+        action_patterns: Vec<RunnerActionPattern>,
+    ) -> Self {
+        assert!(!action_patterns.is_empty());
+        assert!(action_patterns.iter().all(|p| !p.skill_idxs.is_empty()));
+        assert!(action_patterns.iter().any(|p| p.weight > 0));
         assert!({
             let range = 0..skills.len();
             action_patterns
                 .iter()
-                .all(|p| p.iter().all(|idx| range.contains(idx)))
+                .all(|p| p.skill_idxs.iter().all(|idx| range.contains(idx)))
+        });
+        assert_eq!(skills.len(), fallbacks.len());
+        assert!({
+            let range = 0..skills.len();
+            fallbacks.iter().flatten().all(|idx| range.contains(idx))
         });
 
-        let mut s = Self {
+        Self {
             schedule: VecDeque::new(),
             current_frame: 0,
+            fizzled: false,
             action_patterns,
+            fallbacks,
             skills,
-        };
-        s.push_schedule();
-        s
+        }
     }
 
-    pub fn current_condition(&self) -> EnemyCondition {
+    pub fn current_condition(&self) -> EnemyCondition<'_> {
+        let skill = self.get_current_skill();
+
+        let acting_end = skill.start_up_frames + skill.acting_frames();
+
+        let (ty, current, max) = if self.current_frame < skill.start_up_frames {
+            (
+                EnemyConditionType::StartUp,
+                self.current_frame,
+                skill.start_up_frames,
+            )
+        } else if self.current_frame <= acting_end {
+            // 最後の行動が発動するフレームまで行動中とする
+            (
+                EnemyConditionType::Acting,
+                self.current_frame - skill.start_up_frames,
+                skill.acting_frames(),
+            )
+        } else {
+            (
+                EnemyConditionType::Recovery,
+                self.current_frame - acting_end,
+                skill.recovery_frame,
+            )
+        };
+
+        // 硬直が0フレームのスキルは進捗100%として扱う
+        let progress = ProgressState::new(current as f32, max as f32)
+            .unwrap_or(ProgressState::new(1u16, 1u16).unwrap());
+
         EnemyCondition {
-            ty: EnemyConditionType::StartUp,
-            progress: ProgressState::new(1u16, 1u16).unwrap(),
+            ty,
+            progress,
+            skill,
+        }
+    }
+
+    /// 条件を満たしているアクションパターンのindexを返す
+    pub(crate) fn selectable_action_patterns(&self, state: &GameState) -> Vec<usize> {
+        self.action_patterns
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.conditions.iter().all(|c| c.is_satisfied(state)))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// スケジュールを補充し、次に使用するスキルを確定させる
+    ///
+    /// アクションパターンの条件は補充時に加えて、パターンの最初のスキルを開始する時にも確認する。
+    /// 開始時に条件を満たさなくなっていた場合はスケジュールを組み直す。
+    ///
+    /// selectableはselectable_action_patternsの戻り値
+    /// mpは敵の現在のMP
+    pub(crate) fn fill_schedule(&mut self, selectable: &[usize], mp: StatusNum) {
+        if let Some(front) = self.schedule.front()
+            && let Some(pattern_idx) = front.check_pattern
+            && !selectable.contains(&pattern_idx)
+        {
+            self.schedule.clear();
         }
+        self.push_schedule(selectable);
+        self.apply_fallback(mp);
+        debug_assert!(self.schedule.len() >= Self::NUM_VIEW_SKILLS);
+    }
+
+    /// 現在実行中のスキル以外のスケジュールを破棄して組み直す
+    pub(crate) fn flush_schedule(&mut self, selectable: &[usize]) {
+        self.schedule.truncate(1);
+        self.push_schedule(selectable);
+        debug_assert!(self.schedule.len() >= Self::NUM_VIEW_SKILLS);
     }
 
-    fn push_schedule(&mut self) {
+    pub(crate) fn set_action_patterns(&mut self, action_patterns: Vec<RunnerActionPattern>) {
+        assert!(!action_patterns.is_empty());
+        assert!(action_patterns.iter().any(|p| p.weight > 0));
+        // 古いパターンのindexは使えないので、組まれているスキルは条件を確認せずに使う
+        for scheduled in self.schedule.iter_mut() {
+            scheduled.check_pattern = None;
+        }
+        self.action_patterns = action_patterns;
+    }
+
+    fn push_schedule(&mut self, selectable: &[usize]) {
         while self.schedule.len() < Self::NUM_VIEW_SKILLS {
-            let pattern = Self::random_select_action_pattern(&self.action_patterns);
-            pattern.iter().for_each(|skill_idx| {
-                self.schedule.push_back(*skill_idx);
-            });
+            let pattern_idx = self.random_select_action_pattern(selectable);
+            let skill_idxs = &self.action_patterns[pattern_idx].skill_idxs;
+            self.schedule.extend(
+                skill_idxs
+                    .iter()
+                    .enumerate()
+                    .map(|(i, idx)| ScheduledSkill {
+                        skill_idx: *idx,
+                        check_pattern: (i == 0).then_some(pattern_idx),
+                    }),
+            );
         }
     }
 
     pub fn view_skills(&self) -> impl Iterator<Item = &EnemySkill> {
         assert!(self.schedule.len() >= Self::NUM_VIEW_SKILLS);
 
-        let iter = self.schedule.as_slices().0[0..Self::NUM_VIEW_SKILLS]
+        // VecDequeは内部でリングバッファになっているので
+        // as_slicesの最初の要素だけでは足りない場合がある
+        let iter = self
+            .schedule
             .iter()
-            .map(|idx| self.skills.get(*idx).unwrap());
+            .take(Self::NUM_VIEW_SKILLS)
+            .map(|scheduled| self.skills.get(scheduled.skill_idx).unwrap());
 
         debug_assert!(iter.clone().count() == Self::NUM_VIEW_SKILLS);
 
@@ -67,32 +192,107 @@ impl EnemySkillRunnner {
     }
 
     pub fn get_current_skill(&self) -> &EnemySkill {
-        let idx = self.schedule.front().copied().unwrap();
-        self.skills.get(idx).unwrap()
+        let scheduled = self.schedule.front().unwrap();
+        self.skills.get(scheduled.skill_idx).unwrap()
     }
 
     pub fn tick(&self, state: &GameState, effects_buffer: &mut EffectsBuffer) {
-        effects_buffer.push(Effect::EnemySkillRunnerIncrementFrame);
         let current_skill = self.get_current_skill();
-        if self.current_frame == current_skill.start_up_frames {
-            current_skill.run_actions(state, effects_buffer);
+        let is_fire_frame = self.current_frame == current_skill.start_up_frames;
+
+        // 準備中にMPを削られた場合は不発になる
+        // フレームを進めるとスキルがスケジュールから取り除かれる場合があるので、先に不発にする
+        if is_fire_frame && !current_skill.is_mp_enough(state.get_enemy().lt().mp()) {
+            effects_buffer.push(Effect::EnemySkillRunnerFizzle);
+            effects_buffer.push(Effect::EnemySkillRunnerIncrementFrame);
+            return;
+        }
+
+        effects_buffer.push(Effect::EnemySkillRunnerIncrementFrame);
+        if is_fire_frame {
+            effects_buffer.push(Effect::ConsumeMp {
+                target_id: LtId::Enemy,
+                num: current_skill.need_mp,
+            });
+            effects_buffer.push(Effect::EnemySkillFired {
+                skill_id: current_skill.id,
+            });
+        }
+
+        if self.fizzled {
+            return;
         }
+
+        if let Some(acting_frame) = self
+            .current_frame
+            .checked_sub(current_skill.start_up_frames)
+        {
+            current_skill.run_actions(acting_frame, state, effects_buffer);
+        }
+    }
+
+    pub(crate) fn fizzle(&mut self) {
+        self.fizzled = true;
     }
 
-    pub fn increment_frame(&mut self) {
+    /// 現在のスキルが終了した場合trueを返す
+    /// trueを返した場合、呼び出し元はfill_scheduleを呼ぶ必要がある
+    pub(crate) fn increment_frame(&mut self) -> bool {
         self.current_frame += 1;
         if self.current_frame >= self.get_current_skill().total_frames() {
-            self.schedule.pop_front();
-            self.push_schedule();
-            self.current_frame = 0;
-            debug_assert!(self.schedule.len() >= Self::NUM_VIEW_SKILLS);
+            self.cancel_current_skill();
+            return true;
         }
+        false
     }
 
-    fn random_select_action_pattern(action_patterns: &[Vec<usize>]) -> &Vec<usize> {
-        action_patterns
-            .get(rng().random_range(0..action_patterns.len()))
-            .unwrap()
+    /// 現在のスキルを中断してスケジュールから取り除く
+    /// 呼び出し元はfill_scheduleを呼ぶ必要がある
+    pub(crate) fn cancel_current_skill(&mut self) {
+        self.schedule.pop_front();
+        self.current_frame = 0;
+        self.fizzled = false;
+    }
+
+    /// 次に使用するスキルのMPが足りない場合、代わりのスキルに差し替える
+    ///
+    /// 代わりのスキルのMPも足りない場合はさらにその代わりのスキルを辿る。
+    /// 循環している場合に備えてスキルの数以上は辿らない。
+    fn apply_fallback(&mut self, mp: StatusNum) {
+        let current = &mut self.schedule.front_mut().unwrap().skill_idx;
+
+        for _ in 0..self.skills.len() {
+            if self.skills[*current].is_mp_enough(mp) {
+                break;
+            }
+
+            let Some(fallback) = self.fallbacks[*current] else {
+                break;
+            };
+            *current = fallback;
+        }
+    }
+
+    /// 重みに比例した確率でアクションパターンを選ぶ
+    ///
+    /// 重みが正で条件なしのパターンが必ず存在するので、選択候補の重みの合計は0にならない。
+    fn random_select_action_pattern(&self, selectable: &[usize]) -> usize {
+        let total: u32 = selectable
+            .iter()
+            .map(|idx| self.action_patterns[*idx].weight)
+            .sum();
+        assert!(total > 0);
+
+        let mut r = rng().random_range(0..total);
+        for idx in selectable {
+            let weight = self.action_patterns[*idx].weight;
+            if r < weight {
+                return *idx;
+            }
+            r -= weight;
+        }
+
+        unreachable!()
     }
 }
 
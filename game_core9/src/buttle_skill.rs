@@ -1,40 +1,99 @@
-use crate::{TimeNum, game_state::GameState, runtime_id::RuntimeSkillId, skill::SkillBox};
+use crate::{
+    TimeNum,
+    game_state::GameState,
+    runtime_id::RuntimeSkillId,
+    skill::{SkillBox, SkillCost},
+};
 
 #[derive(Debug)]
 pub struct ButtleSkill {
     runtime_id: RuntimeSkillId,
     skill_box: SkillBox,
-    cooldown: TimeNum,
+    /// 使用回数ごとの残りクールダウン。0以下なら使用可能
+    charge_cooldowns: Vec<TimeNum>,
 }
 impl ButtleSkill {
     pub(crate) fn new(runtime_id: RuntimeSkillId, skill_box: SkillBox) -> Self {
+        let max_charges = skill_box.max_charges();
+        assert!(max_charges > 0);
         Self {
             runtime_id,
             skill_box,
-            cooldown: 0.0,
+            charge_cooldowns: vec![0.0; max_charges as usize],
         }
     }
 
     pub fn useable(&self, state: &GameState) -> bool {
+        // 行動不能は他の条件より優先する
+        let owner = state.get_char(self.runtime_id.char_id).lt();
+        if owner.is_stunned() || (owner.is_silenced() && self.skill_box.is_magic()) {
+            return false;
+        }
+
         let custom_useable = self.skill_box.custom_useable(self.runtime_id, state);
-        let need_mp = self.skill_box.cost(self.runtime_id, state).need_mp();
-        let mp_ok = state.get_char(self.runtime_id.char_id).lt().mp() >= need_mp;
-        let cooldown_ok = self.cooldown <= 0.0;
+        let cost = self.cost(state);
+        let mp_ok = owner.mp() >= cost.need_mp();
+        let cooldown_ok = self.charges() > 0;
+        // 消費HPで戦闘不能にはならない
+        let other_cost_ok = (cost.need_hp() <= 0.0 || owner.hp() > cost.need_hp())
+            && cost
+                .need_resource()
+                .is_none_or(|(key, num)| owner.resource(key) >= num);
 
         match custom_useable {
             crate::skill::SkillCustomUseable::Strong(useable) => useable,
-            crate::skill::SkillCustomUseable::IgnoreNeedMp => cooldown_ok,
-            crate::skill::SkillCustomUseable::IgnoreCooldown => mp_ok,
-            crate::skill::SkillCustomUseable::Normal => cooldown_ok && mp_ok,
+            crate::skill::SkillCustomUseable::IgnoreNeedMp => cooldown_ok && other_cost_ok,
+            crate::skill::SkillCustomUseable::IgnoreCooldown => mp_ok && other_cost_ok,
+            crate::skill::SkillCustomUseable::Normal => cooldown_ok && mp_ok && other_cost_ok,
+        }
+    }
+
+    /// 消費MPの倍率を反映したコスト
+    pub fn cost(&self, state: &GameState) -> SkillCost {
+        let cost = self.skill_box.cost(self.runtime_id, state);
+        if cost.need_mp() <= 0.0 {
+            // 効率が0で倍率が無限大になっても、消費MPが0のスキルは0のまま
+            return cost;
         }
+        let mp_cost_mag = state.get_char(self.runtime_id.char_id).lt().mp_cost_mag();
+        cost.with_need_mp(cost.need_mp() * mp_cost_mag)
+    }
+
+    /// 使用可能な回数
+    pub fn charges(&self) -> u32 {
+        self.charge_cooldowns.iter().filter(|c| **c <= 0.0).count() as u32
+    }
+
+    pub fn max_charges(&self) -> u32 {
+        self.charge_cooldowns.len() as u32
+    }
+
+    /// 次に使用回数が回復するまでの残りクールダウン
+    /// 全て回復している場合は0
+    pub fn cooldown(&self) -> TimeNum {
+        self.charge_cooldowns
+            .iter()
+            .copied()
+            .filter(|c| *c > 0.0)
+            .reduce(TimeNum::min)
+            .unwrap_or(0.0)
     }
 
+    /// 全ての使用回数のクールダウンが同時に回復する
     pub fn heal_cooldown(&mut self, num: TimeNum) {
-        self.cooldown -= num;
+        self.charge_cooldowns
+            .iter_mut()
+            .for_each(|c| *c = (*c - num).max(0.0));
     }
 
+    /// 最も早く使用可能になる使用回数に加算する
     pub fn add_cooldown(&mut self, num: TimeNum) {
-        self.cooldown += num;
+        let min = self
+            .charge_cooldowns
+            .iter_mut()
+            .min_by(|a, b| a.total_cmp(b))
+            .unwrap();
+        *min += num;
     }
 
     pub fn skill_box(&self) -> &SkillBox {
@@ -1,10 +1,17 @@
 use std::collections::VecDeque;
 
+use rand::Rng;
+
 use crate::{
-    damage::Damage,
+    StaticPassiveId,
+    any_message::AnyMessageBox,
+    buttle_char::ButtleChar,
+    damage::{Damage, PendingDamage},
     effect::Effect,
-    game_state::{GameState, GameStateArgs, WinOrLose},
-    runtime_id::RuntimeSkillId,
+    game_state::{GameState, GameStateArgs, RNG_SALT_RESIST, WinOrLose},
+    resource::ResourceGeneration,
+    runtime_id::{LtId, RuntimeSkillId},
+    skill::ChantPushback,
 };
 
 pub enum UserInput {
@@ -45,14 +52,42 @@ impl GameCoreActor {
 
         self.state.tick(&mut self.effects_buffer);
 
+        let mut rng = self.state.frame_rng(RNG_SALT_RESIST);
+
         while let Some(effect) = self.effects_buffer.pop_front() {
+            let effect = match effect {
+                Effect::Damage(damage) => match modify_damage(damage, &self.state) {
+                    Some(damage) => Effect::Damage(damage),
+                    None => continue,
+                },
+                // 同じフレームに複数回被弾した場合など
+                Effect::InterruptSkill { skill_id }
+                    if self.state.get_char(skill_id.char_id).current_using_skill()
+                        != Some(skill_id) =>
+                {
+                    continue;
+                }
+                Effect::AddPassive { .. } => {
+                    if let Some(event) = resist_passive(&effect, &self.state, &mut rng) {
+                        output_buffer.push(GameCoreOutput::Event(event));
+                        continue;
+                    }
+                    effect
+                }
+                effect => effect,
+            };
             self.state.accept_effect(&effect);
             sub_effects(&effect, &self.state, &mut self.effects_buffer);
+            if let Some(output_event) = OutputEvent::try_from_effect(&effect, &self.state) {
+                output_buffer.push(GameCoreOutput::Event(output_event));
+            }
             if let Some(output_effect) = OutputEffect::try_from_effect(effect) {
                 output_buffer.push(GameCoreOutput::Effect(output_effect));
             }
         }
 
+        self.state.increment_frame();
+
         if let Some(win_or_lose) = self.state.check_win_or_lose() {
             self.game_ended = true;
             output_buffer.push(GameCoreOutput::Event(win_or_lose.into()));
@@ -74,7 +109,7 @@ fn user_input_effect(
                 return Err(crate::Error::InvalidArgument("Unusable Skill".to_string()));
             }
 
-            let cost = skill.skill_box().cost(skill_id, state);
+            let cost = skill.cost(state);
 
             effects_buffer.push(Effect::UseSkill { skill_id });
 
@@ -92,6 +127,21 @@ fn user_input_effect(
                 target_id: skill_id.owner_id().into(),
                 num: cost.need_mp(),
             });
+
+            if cost.need_hp() > 0.0 {
+                effects_buffer.push(Effect::ConsumeHp {
+                    target_id: skill_id.owner_id().into(),
+                    num: cost.need_hp(),
+                });
+            }
+
+            if let Some((key, num)) = cost.need_resource() {
+                effects_buffer.push(Effect::ConsumeResource {
+                    target_id: skill_id.owner_id().into(),
+                    key,
+                    num,
+                });
+            }
         }
         UserInput::None => {}
     };
@@ -99,14 +149,185 @@ fn user_input_effect(
     Ok(())
 }
 
+/// 攻撃側と被弾側のパッシブでダメージを変更する
+/// 無効化された場合はNone
+pub(crate) fn modify_damage(damage: Damage, state: &GameState) -> Option<Damage> {
+    let causer = damage.causer().to_lt_id();
+    let target = damage.target();
+
+    // (攻撃側か, 所有者, パッシブ)
+    // スタックしているパッシブはスタック数の回数だけ変更する
+    let mut modifiers = Vec::new();
+    if let Some(causer) = causer {
+        state
+            .get_lt(causer)
+            .passive
+            .stacked_instances()
+            .for_each(|p| modifiers.push((true, causer, p)));
+    }
+    state
+        .get_lt(target)
+        .passive
+        .stacked_instances()
+        .for_each(|p| modifiers.push((false, target, p)));
+
+    // 安定ソートなので同じ順番の場合は攻撃側、被弾側の順で追加された順になる
+    modifiers.sort_by_key(|(_, _, p)| p.damage_modify_order());
+
+    let mut pending = PendingDamage::new(damage);
+    for (is_causer, owner, passive) in modifiers {
+        if pending.is_nullified() {
+            break;
+        }
+        if is_causer {
+            passive.modify_deal_damage(owner, &mut pending, state);
+        } else if pending.damage().target() == target {
+            // 対象が変更された後は元の対象のパッシブで変更しない
+            passive.modify_recv_damage(owner, &mut pending, state);
+        }
+    }
+
+    pending.into_damage()
+}
+
+/// 対象がパッシブに耐性を持っていて付与を防いだ場合はイベントを返す
+fn resist_passive(effect: &Effect, state: &GameState, rng: &mut impl Rng) -> Option<OutputEvent> {
+    let Effect::AddPassive {
+        target_id, passive, ..
+    } = effect
+    else {
+        return None;
+    };
+
+    let info = passive.info();
+    let resistance = state.get_lt(*target_id).passive_resistance(info);
+    if resistance >= 1.0 {
+        Some(OutputEvent::PassiveImmune {
+            target_id: *target_id,
+            passive_id: info.id,
+        })
+    } else if resistance > 0.0 && rng.random_bool(resistance as f64) {
+        Some(OutputEvent::PassiveResisted {
+            target_id: *target_id,
+            passive_id: info.id,
+        })
+    } else {
+        None
+    }
+}
+
+/// 詠唱中に被弾した場合、スキルの設定に応じて詠唱を戻すか中断する
+fn chant_damaged(char: &ButtleChar, damage: &Damage, effects_buffer: &mut EffectsBuffer) {
+    let Some(skill_id) = char.current_using_skill() else {
+        return;
+    };
+    let lt = char.lt();
+    if !char.is_chanting() || lt.is_uninterruptible() || lt.is_dead() || damage.dmg() <= 0.0 {
+        return;
+    }
+
+    let rule = char.get_skill(skill_id).skill_box().chant_interruption();
+    if rule
+        .interrupt_threshold
+        .is_some_and(|t| damage.dmg() >= lt.max_hp() * t)
+    {
+        effects_buffer.push(Effect::InterruptSkill { skill_id });
+    } else if rule.pushback_ms > 0.0 {
+        effects_buffer.push(Effect::UpdateSkillState {
+            skill_id,
+            msg: AnyMessageBox::new(ChantPushback {
+                ms: rule.pushback_ms,
+            }),
+        });
+    }
+}
+
 fn sub_effects(effect: &Effect, state: &GameState, effects_buffer: &mut EffectsBuffer) {
-    if let Effect::Damage(damage) = effect {
-        state.get_lt(damage.target()).passive.trigger_recv_damage(
-            damage.target(),
-            damage,
-            state,
-            effects_buffer,
-        );
+    match effect {
+        Effect::Damage(damage) => {
+            let target = damage.target();
+            state
+                .get_lt(target)
+                .passive
+                .trigger_recv_damage(target, damage, state, effects_buffer);
+
+            state
+                .get_lt(target)
+                .generate_resources(target, effects_buffer, |g| match g {
+                    ResourceGeneration::RecvDamage(per) => Some(per * damage.dmg()),
+                    _ => None,
+                });
+
+            if let Some(causer) = damage.causer().to_lt_id() {
+                state.get_lt(causer).passive.instances().for_each(|p| {
+                    p.trigger_deal_damage(causer, damage, state, effects_buffer);
+                });
+                state
+                    .get_lt(causer)
+                    .generate_resources(causer, effects_buffer, |g| match g {
+                        ResourceGeneration::DealDamage(per) => Some(per * damage.dmg()),
+                        _ => None,
+                    });
+            }
+
+            if let LtId::Char(char_id) = target {
+                chant_damaged(state.get_char(char_id), damage, effects_buffer);
+            }
+
+            // 敵には味方がいない
+            if let LtId::Char(_) = target {
+                for ally in state.get_chars().iter().filter(|c| c.lt_id() != target) {
+                    ally.lt().passive.instances().for_each(|p| {
+                        p.trigger_ally_damaged(ally.lt_id(), damage, state, effects_buffer);
+                    });
+                }
+            }
+
+            // このダメージで死亡した場合のみ
+            let target_lt = state.get_lt(target);
+            if target_lt.is_dead() && target_lt.hp() + damage.dmg() > 0.0 {
+                target_lt.passive.instances().for_each(|p| {
+                    p.trigger_owner_death(target, state, effects_buffer);
+                });
+            }
+        }
+        Effect::HealHp { target_id, num } => {
+            state.get_lt(*target_id).passive.instances().for_each(|p| {
+                p.trigger_healed(*target_id, *num, state, effects_buffer);
+            });
+        }
+        Effect::ConsumeMp { target_id, num } => {
+            state.get_lt(*target_id).passive.instances().for_each(|p| {
+                p.trigger_consume_mp(*target_id, *num, state, effects_buffer);
+            });
+        }
+        Effect::UseSkill { skill_id } => {
+            let owner = skill_id.owner_id().into();
+            state.get_lt(owner).passive.instances().for_each(|p| {
+                p.trigger_skill_start(owner, *skill_id, state, effects_buffer);
+            });
+        }
+        Effect::EndSkill { skill_id } | Effect::InterruptSkill { skill_id } => {
+            let owner = skill_id.owner_id().into();
+            state.get_lt(owner).passive.instances().for_each(|p| {
+                p.trigger_skill_end(owner, *skill_id, state, effects_buffer);
+            });
+        }
+        Effect::EnemySkillFired { skill_id } => {
+            for (owner, lt) in state.get_lts() {
+                lt.passive.instances().for_each(|p| {
+                    p.trigger_enemy_skill_fired(owner, *skill_id, state, effects_buffer);
+                });
+            }
+        }
+        Effect::BattleStart => {
+            for (owner, lt) in state.get_lts() {
+                lt.passive.instances().for_each(|p| {
+                    p.trigger_battle_start(owner, state, effects_buffer);
+                });
+            }
+        }
+        _ => {}
     }
 }
 
@@ -118,6 +339,58 @@ pub enum GameCoreOutput {
 pub enum OutputEvent {
     Win,
     Lose,
+    EnemyPhaseChanged {
+        phase: usize,
+        name: &'static str,
+    },
+    EnemyBroken,
+    /// 耐性によりパッシブが付与されなかった
+    PassiveImmune {
+        target_id: LtId,
+        passive_id: StaticPassiveId,
+    },
+    /// 耐性の確率によりパッシブが付与されなかった
+    PassiveResisted {
+        target_id: LtId,
+        passive_id: StaticPassiveId,
+    },
+    /// 被弾により詠唱が中断された
+    SkillInterrupted {
+        skill_id: RuntimeSkillId,
+    },
+    /// 効果時間が切れてパッシブが取り除かれた
+    PassiveExpired {
+        target_id: LtId,
+        passive_id: StaticPassiveId,
+    },
+}
+impl OutputEvent {
+    fn try_from_effect(effect: &Effect, state: &GameState) -> Option<Self> {
+        match effect {
+            Effect::EnemyChangePhase { .. } => {
+                let enemy = state.get_enemy();
+                Some(OutputEvent::EnemyPhaseChanged {
+                    phase: enemy.current_phase(),
+                    name: enemy.current_phase_name()?,
+                })
+            }
+            Effect::EnemyBreak => Some(OutputEvent::EnemyBroken),
+            Effect::InterruptSkill { skill_id } => Some(OutputEvent::SkillInterrupted {
+                skill_id: *skill_id,
+            }),
+            // 付与元ごとに独立したパッシブの一部だけが切れた場合は通知しない
+            Effect::ExpirePassive {
+                target_id,
+                passive_id,
+            } if !state.get_lt(*target_id).passive.contains(*passive_id) => {
+                Some(OutputEvent::PassiveExpired {
+                    target_id: *target_id,
+                    passive_id: *passive_id,
+                })
+            }
+            _ => None,
+        }
+    }
 }
 impl From<WinOrLose> for OutputEvent {
     fn from(value: WinOrLose) -> Self {
@@ -71,7 +71,26 @@ impl Damage {
     }
 }
 
+/// ブレイク中の敵への被ダメージ倍率
+fn broken_recv_dmg_mag(state: &GameState, target_id: LtId) -> StatusNum {
+    match target_id {
+        LtId::Enemy => state.get_enemy().broken_recv_dmg_mag(),
+        LtId::Char(_) => 1.0,
+    }
+}
+
 impl Damage {
+    /// ステータスに関係なく決まった量のダメージ
+    pub fn new_fixed_damage(causer: Option<LtId>, target_id: LtId, dmg: StatusNum) -> Self {
+        assert!(dmg >= 0.0);
+        Self {
+            causer: causer.map_or(DamageCauser::None, Into::into),
+            target: target_id,
+            ty: DamageType::Fixed,
+            dmg,
+        }
+    }
+
     pub fn new_hp_per_dmg(state: &GameState, target_id: LtId, per: StatusNum) -> Self {
         assert!(per >= 0.0);
         let target = state.get_lt(target_id);
@@ -95,7 +114,10 @@ impl Damage {
         let attucker = state.get_lt(attucker_id);
         let target = state.get_lt(target_id);
 
-        let dmg = attucker.magic_attuck() * target.recv_magic_dmg_mag() * dmg_mag;
+        let dmg = attucker.magic_attuck()
+            * target.recv_magic_dmg_mag()
+            * broken_recv_dmg_mag(state, target_id)
+            * dmg_mag;
 
         Self {
             causer: attucker_id.into(),
@@ -116,7 +138,10 @@ impl Damage {
         let attucker = state.get_lt(attucker_id);
         let target = state.get_lt(target_id);
 
-        let dmg = attucker.physics_attuck() * target.recv_physics_dmg_mag() * dmg_mag;
+        let dmg = attucker.physics_attuck()
+            * target.recv_physics_dmg_mag()
+            * broken_recv_dmg_mag(state, target_id)
+            * dmg_mag;
 
         Self {
             causer: attucker_id.into(),
@@ -141,3 +166,60 @@ impl Damage {
         }
     }
 }
+
+/// 適用前のダメージ
+/// 攻撃側と被弾側のパッシブが変更する
+#[derive(Debug, Clone)]
+pub struct PendingDamage {
+    damage: Damage,
+    nullified: bool,
+}
+
+impl PendingDamage {
+    pub(crate) fn new(damage: Damage) -> Self {
+        Self {
+            damage,
+            nullified: false,
+        }
+    }
+
+    pub fn damage(&self) -> &Damage {
+        &self.damage
+    }
+
+    pub fn is_nullified(&self) -> bool {
+        self.nullified
+    }
+
+    pub fn mul(&mut self, mag: StatusNum) {
+        assert!(mag >= 0.0);
+        self.damage.dmg *= mag;
+    }
+
+    /// 0未満にはならない
+    pub fn sub(&mut self, num: StatusNum) {
+        self.damage.dmg = (self.damage.dmg - num).max(0.0);
+    }
+
+    /// ダメージの上限
+    pub fn cap(&mut self, max: StatusNum) {
+        assert!(max >= 0.0);
+        self.damage.dmg = self.damage.dmg.min(max);
+    }
+
+    /// 対象を変更する
+    /// 変更先のパッシブによる変更は行われず、変更後は元の対象のパッシブによる変更も行われない
+    pub fn redirect(&mut self, target: LtId) {
+        self.damage.target = target;
+    }
+
+    /// ダメージ自体をなかったことにする
+    pub fn nullify(&mut self) {
+        self.nullified = true;
+    }
+
+    /// 無効化された場合はNone
+    pub(crate) fn into_damage(self) -> Option<Damage> {
+        (!self.nullified).then_some(self.damage)
+    }
+}
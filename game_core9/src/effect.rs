@@ -1,14 +1,35 @@
 use crate::{
-    StaticPassiveId, StatusNum, TimeNum,
+    StaticEnemySkillId, StaticPassiveId, StatusNum, TimeNum,
     any_message::AnyMessageBox,
     damage,
-    passive::PassiveBox,
+    game_state::GameState,
+    passive::{PassiveBox, PassiveFilter, status::PassiveStatus},
+    resource::ResourceKey,
     runtime_id::{LtId, RuntimeCharId, RuntimeSkillId},
 };
 
 #[derive(Debug, Clone)]
 pub enum Effect {
     EnemySkillRunnerIncrementFrame,
+    /// 1以上溜まるとEnemySkillRunnerIncrementFrameで1消費される
+    EnemyAddFrameProgress {
+        num: TimeNum,
+    },
+    /// 実行中のスキルをMP不足で不発にする
+    EnemySkillRunnerFizzle,
+    /// 実行中のスキルを中断してブレイク状態になる
+    EnemyBreak,
+    EnemyDecrementBrokenFrame,
+    /// 0は初期状態。Nの場合はButtleEnemyArgs::phases[N - 1]のフェーズ
+    EnemyChangePhase {
+        phase: usize,
+    },
+    /// 戦闘開始時に一度だけ発生する
+    BattleStart,
+    /// 敵のスキルの準備が終わり、発動した
+    EnemySkillFired {
+        skill_id: StaticEnemySkillId,
+    },
     Damage(damage::Damage),
     HealHp {
         target_id: LtId,
@@ -18,6 +39,23 @@ pub enum Effect {
         target_id: LtId,
         num: StatusNum,
     },
+    /// スキルのコストなどでHPを消費する。ダメージとしては扱わない
+    ConsumeHp {
+        target_id: LtId,
+        num: StatusNum,
+    },
+    /// HP/MP以外のリソースを獲得する。最大値を超えない
+    AddResource {
+        target_id: LtId,
+        key: ResourceKey,
+        num: StatusNum,
+    },
+    /// HP/MP以外のリソースを消費する。0未満にはならない
+    ConsumeResource {
+        target_id: LtId,
+        key: ResourceKey,
+        num: StatusNum,
+    },
     HealMp {
         target_id: LtId,
         num: StatusNum,
@@ -30,6 +68,48 @@ pub enum Effect {
     AddPassive {
         target_id: LtId,
         passive: PassiveBox,
+        /// 付与元
+        source: Option<LtId>,
+    },
+    /// PassiveTrait::conditional_statusや受けているオーラの評価結果が変わった
+    UpdateConditionalPassiveStatus {
+        target_id: LtId,
+        status: Box<PassiveStatus>,
+    },
+    /// 存在しない場合は何もしない
+    RemovePassive {
+        target_id: LtId,
+        passive_id: StaticPassiveId,
+    },
+    /// 条件に合うパッシブを新しく追加されたものから最大num個取り除く
+    /// 敵のバフ解除や味方のデバフ解除に使う
+    DispelPassives {
+        target_id: LtId,
+        filter: PassiveFilter,
+        num: usize,
+    },
+    /// 効果時間が切れたパッシブを取り除く
+    ExpirePassive {
+        target_id: LtId,
+        passive_id: StaticPassiveId,
+    },
+    DecrementPassiveRemainingFrames {
+        target_id: LtId,
+    },
+    /// パッシブが付与されてからの経過フレームを進める
+    IncrementPassiveElapsedFrames {
+        target_id: LtId,
+    },
+    /// パッシブの残り時間を本来の効果時間に戻す
+    RefreshPassiveDuration {
+        target_id: LtId,
+        passive_id: StaticPassiveId,
+    },
+    /// パッシブの残り時間を延長する
+    ExtendPassiveDuration {
+        target_id: LtId,
+        passive_id: StaticPassiveId,
+        frames: u64,
     },
     AddSkillCooldown {
         skill_id: RuntimeSkillId,
@@ -43,6 +123,22 @@ pub enum Effect {
         target_id: RuntimeCharId,
         num: StatusNum,
     },
+    /// ヘイトは0未満にならない
+    ReduceHate {
+        target_id: RuntimeCharId,
+        num: StatusNum,
+    },
+    /// ヘイトに乗算する。0.0でリセット
+    MulHate {
+        target_id: RuntimeCharId,
+        mag: StatusNum,
+    },
+    /// fromのヘイトのperの割合をtoに移す
+    TransferHate {
+        from: RuntimeCharId,
+        to: RuntimeCharId,
+        per: HateTransferPer,
+    },
     UpdateSkillState {
         skill_id: RuntimeSkillId,
         msg: AnyMessageBox,
@@ -50,7 +146,48 @@ pub enum Effect {
     UseSkill {
         skill_id: RuntimeSkillId,
     },
+    /// 被弾による詠唱の中断。使用中のスキルでない場合は何もしない
+    InterruptSkill {
+        skill_id: RuntimeSkillId,
+    },
     EndSkill {
         skill_id: RuntimeSkillId,
     },
 }
+
+impl Effect {
+    /// 回復する側と回復される側の倍率を反映したHP回復
+    pub fn new_heal_hp(
+        state: &GameState,
+        healer_id: Option<LtId>,
+        target_id: LtId,
+        num: StatusNum,
+    ) -> Self {
+        assert!(num >= 0.0);
+        let deal_heal_mag = healer_id.map_or(1.0, |id| state.get_lt(id).deal_heal_mag());
+        Effect::HealHp {
+            target_id,
+            num: num * deal_heal_mag * state.get_lt(target_id).recv_heal_mag(),
+        }
+    }
+}
+
+/// Effect::TransferHateで移すヘイトの割合
+/// 0.0 ~ 1.0が保証されている
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HateTransferPer(StatusNum);
+
+impl HateTransferPer {
+    pub fn new(per: StatusNum) -> Result<Self, crate::Error> {
+        if !(0.0..=1.0).contains(&per) {
+            return Err(crate::Error::InvalidArgument(format!(
+                "Hate transfer per must be in 0.0..=1.0: {per}"
+            )));
+        }
+        Ok(Self(per))
+    }
+
+    pub fn get(&self) -> StatusNum {
+        self.0
+    }
+}
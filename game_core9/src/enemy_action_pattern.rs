@@ -0,0 +1,67 @@
+use crate::{
+    StaticEnemySkillId, StaticPassiveId, StatusNum, game_state::GameState, weapon::WeaponType,
+};
+
+#[derive(Debug, Clone)]
+pub struct EnemyActionPattern {
+    pub skills: Vec<StaticEnemySkillId>,
+    /// 選ばれやすさ。条件を満たすパターンの中から重みに比例した確率で選ばれる
+    pub weight: u32,
+    /// 全ての条件を満たす場合のみ選択候補になる
+    /// 条件なしで重みが正のパターンが1つ以上必要
+    ///
+    /// スケジュールの補充時に加えて、パターンの最初のスキルを開始する時にも確認される
+    pub conditions: Vec<EnemyActionCondition>,
+}
+
+impl EnemyActionPattern {
+    /// 重み1、条件なしのパターン
+    pub fn new(skills: Vec<StaticEnemySkillId>) -> Self {
+        Self {
+            skills,
+            weight: 1,
+            conditions: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum EnemyActionCondition {
+    /// 自身のHPの割合がこの値未満 (0.0 ~ 1.0)
+    HpBelow(StatusNum),
+    /// 自身のHPの割合がこの値以上 (0.0 ~ 1.0)
+    HpAtLeast(StatusNum),
+    /// 自身がパッシブを持っている
+    HasPassive(StaticPassiveId),
+    /// 自身がパッシブを持っていない
+    NotHasPassive(StaticPassiveId),
+    /// 生存しているキャラクターの数がこの値以上
+    AliveCharsAtLeast(u8),
+    /// 生存しているキャラクターの数がこの値以下
+    AliveCharsAtMost(u8),
+    /// 戦闘開始からの経過フレームがこの値以上
+    ElapsedFramesAtLeast(u64),
+    /// 戦闘開始からの経過フレームがこの値未満
+    ElapsedFramesBelow(u64),
+    /// ヘイトが最も高いキャラクターの武器種
+    HighestHateWeapon(WeaponType),
+}
+
+impl EnemyActionCondition {
+    pub(crate) fn is_satisfied(&self, state: &GameState) -> bool {
+        let enemy = state.get_enemy().lt();
+        match self {
+            EnemyActionCondition::HpBelow(per) => enemy.hp_per() < *per,
+            EnemyActionCondition::HpAtLeast(per) => enemy.hp_per() >= *per,
+            EnemyActionCondition::HasPassive(id) => enemy.passive.contains(*id),
+            EnemyActionCondition::NotHasPassive(id) => !enemy.passive.contains(*id),
+            EnemyActionCondition::AliveCharsAtLeast(n) => state.num_alive_chars() >= *n as usize,
+            EnemyActionCondition::AliveCharsAtMost(n) => state.num_alive_chars() <= *n as usize,
+            EnemyActionCondition::ElapsedFramesAtLeast(frame) => state.elapsed_frames() >= *frame,
+            EnemyActionCondition::ElapsedFramesBelow(frame) => state.elapsed_frames() < *frame,
+            EnemyActionCondition::HighestHateWeapon(ty) => {
+                state.get_highest_hate_char().weapon_type() == *ty
+            }
+        }
+    }
+}
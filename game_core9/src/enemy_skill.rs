@@ -1,10 +1,13 @@
+use rand::seq::IteratorRandom;
+
 use crate::{
-    MAX_CHARACTERS, StaticEnemySkillId, StatusNum,
-    core_actor::EffectsBuffer,
+    MAX_CHARACTERS, StaticEnemySkillId, StaticPassiveId, StatusNum,
+    buttle_char::ButtleChar,
+    core_actor::{EffectsBuffer, modify_damage},
     damage::{Damage, DamageType},
     effect::Effect,
-    game_state::GameState,
-    passive::PassiveBox,
+    game_state::{GameState, RNG_SALT_ENEMY_SKILL_TARGET},
+    passive::{PassiveBox, PassiveFilter},
     runtime_id::LtId,
 };
 
@@ -13,30 +16,54 @@ pub struct EnemySkill {
     pub id: StaticEnemySkillId,
     pub name: &'static str,
     pub need_mp: f32,
+    /// 準備フレーム。このフレームで最初の行動が発動する
     pub start_up_frames: u64,
+    /// 最後の行動が発動してからの硬直フレーム
     pub recovery_frame: u64,
+    /// frameの昇順で並んでいる必要がある
+    /// 最初の要素のframeは0である必要がある
+    pub actions: Vec<EnemySkillActionGroup>,
+    /// MPが足りない場合に代わりに使用するスキル
+    /// Noneの場合、MPが足りなければスキルは不発になる
+    pub fallback: Option<StaticEnemySkillId>,
+}
+
+/// 同じフレームで発動する行動のまとめ
+#[derive(Debug, Clone)]
+pub struct EnemySkillActionGroup {
+    /// start_up_framesからの相対フレーム
+    pub frame: u64,
     pub actions: Vec<(EnemySkillTarget, EnemySkillAction)>,
 }
 
+impl EnemySkillActionGroup {
+    pub fn new(frame: u64, actions: Vec<(EnemySkillTarget, EnemySkillAction)>) -> Self {
+        Self { frame, actions }
+    }
+}
+
 impl EnemySkill {
-    pub(crate) fn run_actions(&self, state: &GameState, effects_buffer: &mut EffectsBuffer) {
+    /// acting_frameはstart_up_framesからの相対フレーム
+    pub(crate) fn run_actions(
+        &self,
+        acting_frame: u64,
+        state: &GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
         let mut target_ids = Vec::<LtId>::with_capacity(MAX_CHARACTERS + 1);
 
-        for (target, action) in self.actions.iter() {
+        let actions = self
+            .actions
+            .iter()
+            .filter(|g| g.frame == acting_frame)
+            .flat_map(|g| g.actions.iter());
+
+        for (target, action) in actions {
             target.push_ids(state, &mut target_ids);
             for target_id in target_ids.drain(..) {
                 match action {
                     EnemySkillAction::Damage { ty, dmg_mag, count } => {
-                        let dmg = match ty {
-                            DamageType::Magic => {
-                                Damage::new_magic_damage(state, LtId::Enemy, target_id, *dmg_mag)
-                            }
-                            DamageType::Physics => {
-                                Damage::new_physics_damage(state, LtId::Enemy, target_id, *dmg_mag)
-                            }
-                            DamageType::Fixed => unimplemented!(),
-                        };
-
+                        let dmg = new_damage(state, target_id, *ty, *dmg_mag);
                         for _ in 0..*count {
                             effects_buffer.push(Effect::Damage(dmg.clone()));
                         }
@@ -45,6 +72,35 @@ impl EnemySkill {
                         effects_buffer.push(Effect::AddPassive {
                             target_id,
                             passive: passive_box.clone(),
+                            source: Some(LtId::Enemy),
+                        });
+                    }
+                    EnemySkillAction::HealMp(num) => {
+                        effects_buffer.push(Effect::HealMp {
+                            target_id,
+                            num: *num,
+                        });
+                    }
+                    EnemySkillAction::BurnMp(num) => {
+                        effects_buffer.push(Effect::ConsumeMp {
+                            target_id,
+                            num: *num,
+                        });
+                    }
+                    EnemySkillAction::DrainMp(num) => {
+                        // 対象の残りMP以上は吸収できない
+                        let num = num.min(state.get_lt(target_id).mp().max(0.0));
+                        effects_buffer.push(Effect::ConsumeMp { target_id, num });
+                        effects_buffer.push(Effect::HealMp {
+                            target_id: LtId::Enemy,
+                            num,
+                        });
+                    }
+                    EnemySkillAction::DispelPassives { filter, num } => {
+                        effects_buffer.push(Effect::DispelPassives {
+                            target_id,
+                            filter: *filter,
+                            num: *num,
                         });
                     }
                 }
@@ -52,11 +108,117 @@ impl EnemySkill {
         }
     }
 
+    /// 現在の状態でこのスキルを使用した場合に与えるダメージを対象ごとに見積もる
+    ///
+    /// 対象の選択とダメージの計算は現在のヘイトとステータスで行うため、
+    /// 実際に発動するまでに状態が変わると結果も変わる。
+    /// ダメージは実際の被弾と同じくパッシブによる変更を適用した後の値。
+    /// 同じ対象への同じ種類のダメージは合算される。
+    pub fn forecast_damages(&self, state: &GameState) -> Vec<ForecastDamage> {
+        let mut target_ids = Vec::<LtId>::with_capacity(MAX_CHARACTERS + 1);
+        let mut damages = Vec::<ForecastDamage>::new();
+
+        for (target, action) in self.actions.iter().flat_map(|g| g.actions.iter()) {
+            let EnemySkillAction::Damage { ty, dmg_mag, count } = action else {
+                continue;
+            };
+
+            if let EnemySkillTarget::Random(n) = target {
+                // 対象は発動するまで決まらないので候補の中で最大のダメージを見積もる
+                let dmg = state
+                    .get_chars()
+                    .iter()
+                    .filter_map(|c| forecast_damage(state, c.lt_id(), *ty, *dmg_mag))
+                    .map(|d| d.dmg())
+                    .fold(0.0, StatusNum::max);
+                let num = (*n as usize).min(state.get_chars().len()) as u8;
+                ForecastDamage::push(
+                    &mut damages,
+                    ForecastTarget::Random(num),
+                    *ty,
+                    dmg * *count as f32,
+                );
+                continue;
+            }
+
+            target.push_ids(state, &mut target_ids);
+            for target_id in target_ids.drain(..) {
+                // 無効化された場合は含めない
+                let Some(dmg) = forecast_damage(state, target_id, *ty, *dmg_mag) else {
+                    continue;
+                };
+                ForecastDamage::push(
+                    &mut damages,
+                    ForecastTarget::Lt(dmg.target()),
+                    *ty,
+                    dmg.dmg() * *count as f32,
+                );
+            }
+        }
+
+        damages
+    }
+
+    pub(crate) fn is_mp_enough(&self, mp: StatusNum) -> bool {
+        mp >= self.need_mp
+    }
+
+    /// 最初の行動から最後の行動までのフレーム数
+    pub fn acting_frames(&self) -> u64 {
+        self.actions.last().map(|g| g.frame).unwrap_or(0)
+    }
+
     pub(crate) fn total_frames(&self) -> u64 {
-        self.start_up_frames + self.recovery_frame
+        self.start_up_frames + self.acting_frames() + self.recovery_frame
+    }
+}
+
+fn new_damage(state: &GameState, target_id: LtId, ty: DamageType, dmg_mag: StatusNum) -> Damage {
+    match ty {
+        DamageType::Magic => Damage::new_magic_damage(state, LtId::Enemy, target_id, dmg_mag),
+        DamageType::Physics => Damage::new_physics_damage(state, LtId::Enemy, target_id, dmg_mag),
+        DamageType::Fixed => Damage::new_fixed_damage(Some(LtId::Enemy), target_id, dmg_mag),
     }
 }
 
+/// パッシブによる変更を適用したダメージ。無効化された場合はNone
+fn forecast_damage(
+    state: &GameState,
+    target_id: LtId,
+    ty: DamageType,
+    dmg_mag: StatusNum,
+) -> Option<Damage> {
+    modify_damage(new_damage(state, target_id, ty, dmg_mag), state)
+}
+
+/// EnemySkill::forecast_damagesの結果
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForecastDamage {
+    pub target: ForecastTarget,
+    pub ty: DamageType,
+    pub dmg: StatusNum,
+}
+impl ForecastDamage {
+    /// 同じ対象への同じ種類のダメージは合算する
+    fn push(damages: &mut Vec<Self>, target: ForecastTarget, ty: DamageType, dmg: StatusNum) {
+        match damages
+            .iter_mut()
+            .find(|d| d.target == target && d.ty == ty)
+        {
+            Some(d) => d.dmg += dmg,
+            None => damages.push(Self { target, ty, dmg }),
+        }
+    }
+}
+
+/// 見積もったダメージの対象
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ForecastTarget {
+    Lt(LtId),
+    /// ランダムに選ばれるN人。ダメージは1人あたりの最大値
+    Random(u8),
+}
+
 #[derive(Debug, Clone)]
 pub enum EnemySkillTarget {
     Self_,
@@ -64,14 +226,33 @@ pub enum EnemySkillTarget {
     Multi(u8),
     AllChar,
     AllLt,
+    /// ランダムにN人選ぶ。GameState::frame_rngを使うので同じフレームなら同じ結果になる
+    Random(u8),
+    /// HPが最も低いキャラクター
+    LowestHp,
+    /// HPの割合が最も低いキャラクター
+    LowestHpPer,
+    /// ヘイトが最も低いキャラクター
+    LowestHate,
+    /// 物理攻撃力と魔法攻撃力の高い方が最も高いキャラクター
+    HighestAttuck,
+    /// スキルを詠唱中の全てのキャラクター
+    Chanting,
+    /// パッシブを持っている全てのキャラクター
+    WithPassive(StaticPassiveId),
+    /// パッシブを持っていない全てのキャラクター
+    WithoutPassive(StaticPassiveId),
 }
 impl EnemySkillTarget {
     fn push_ids(&self, state: &GameState, ids: &mut Vec<LtId>) {
         match self {
             EnemySkillTarget::Self_ => ids.push(LtId::Enemy),
-            EnemySkillTarget::Single => ids.push(state.get_highest_hate_char().lt_id()),
+            EnemySkillTarget::Single => {
+                let chars = state.get_chars_sorted_by_target_priority();
+                ids.push(chars.first().unwrap().lt_id());
+            }
             EnemySkillTarget::Multi(n) => {
-                let chars = state.get_chars_sorted_by_hate();
+                let chars = state.get_chars_sorted_by_target_priority();
                 chars.iter().take(*n as usize).for_each(|c| {
                     ids.push(c.lt_id());
                 });
@@ -91,16 +272,76 @@ impl EnemySkillTarget {
                     .map(|c| c.lt_id())
                     .for_each(|id| ids.push(id));
             }
+            EnemySkillTarget::Random(n) => {
+                let chars = state.get_chars().iter().choose_multiple(
+                    &mut state.frame_rng(RNG_SALT_ENEMY_SKILL_TARGET),
+                    *n as usize,
+                );
+                chars.iter().for_each(|c| ids.push(c.lt_id()));
+            }
+            EnemySkillTarget::LowestHp => {
+                Self::push_min_by(state, ids, |c| c.lt().hp());
+            }
+            EnemySkillTarget::LowestHpPer => {
+                Self::push_min_by(state, ids, |c| c.lt().hp_per());
+            }
+            EnemySkillTarget::LowestHate => {
+                Self::push_min_by(state, ids, |c| c.hate());
+            }
+            EnemySkillTarget::HighestAttuck => {
+                Self::push_min_by(state, ids, |c| {
+                    -c.lt().physics_attuck().max(c.lt().magic_attuck())
+                });
+            }
+            EnemySkillTarget::Chanting => {
+                Self::push_filtered(state, ids, |c| c.is_chanting());
+            }
+            EnemySkillTarget::WithPassive(id) => {
+                Self::push_filtered(state, ids, |c| c.lt().passive.contains(*id));
+            }
+            EnemySkillTarget::WithoutPassive(id) => {
+                Self::push_filtered(state, ids, |c| !c.lt().passive.contains(*id));
+            }
         }
     }
+
+    /// 値が最も小さいキャラクターを1人選ぶ。同じ値の場合は先頭のキャラクター
+    fn push_min_by(state: &GameState, ids: &mut Vec<LtId>, f: impl Fn(&ButtleChar) -> StatusNum) {
+        let char = state
+            .get_chars()
+            .iter()
+            .min_by(|a, b| f(a).total_cmp(&f(b)))
+            .unwrap();
+        ids.push(char.lt_id());
+    }
+
+    fn push_filtered(state: &GameState, ids: &mut Vec<LtId>, f: impl Fn(&ButtleChar) -> bool) {
+        state
+            .get_chars()
+            .iter()
+            .filter(|c| f(c))
+            .for_each(|c| ids.push(c.lt_id()));
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum EnemySkillAction {
     Damage {
         ty: DamageType,
+        /// 固定ダメージの場合はダメージ量そのもの
         dmg_mag: StatusNum,
         count: u8,
     },
     AddPassive(PassiveBox),
+    /// 対象のMPを回復する
+    HealMp(StatusNum),
+    /// 対象のMPを減らす
+    BurnMp(StatusNum),
+    /// 対象のMPを減らし、減らした分だけ敵のMPを回復する
+    DrainMp(StatusNum),
+    /// 条件に合うパッシブを新しいものから最大num個解除する
+    DispelPassives {
+        filter: PassiveFilter,
+        num: usize,
+    },
 }
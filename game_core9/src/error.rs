@@ -10,4 +10,7 @@ pub enum Error {
 
     #[error("NotFoundSkillInActionPattern")]
     NotFoundSkillInActionPattern,
+
+    #[error("NotFoundFallbackSkill")]
+    NotFoundFallbackSkill,
 }
@@ -1,23 +1,35 @@
+use rand::{SeedableRng, rngs::StdRng};
+
 use crate::{
     MAX_CHARACTERS,
     buttle_char::{ButtleChar, ButtleCharArgs},
     buttle_enemy::{ButtleEnemy, ButtleEnemyArgs},
     buttle_skill::ButtleSkill,
     core_actor::EffectsBuffer,
+    damage::DamageCauser,
     effect::Effect,
     lt_common::LtCommon,
     runtime_id::{LtId, RuntimeCharId, RuntimeSkillId},
 };
 
+/// 敵スキルのランダムな対象選択に使うframe_rngのsalt
+pub(crate) const RNG_SALT_ENEMY_SKILL_TARGET: u64 = 1;
+/// パッシブの抵抗判定に使うframe_rngのsalt
+pub(crate) const RNG_SALT_RESIST: u64 = 2;
+
 pub struct GameStateArgs {
     pub chars: Vec<ButtleCharArgs>,
     pub enemy: ButtleEnemyArgs,
+    /// 同じシードなら同じ乱数列になる
+    pub seed: u64,
 }
 
 #[derive(Debug)]
 pub struct GameState {
     chars: Vec<ButtleChar>,
     enemy: ButtleEnemy,
+    elapsed_frames: u64,
+    seed: u64,
 }
 
 //--------------------------------------------------//
@@ -42,10 +54,27 @@ impl GameState {
 
         let enemy = ButtleEnemy::new(args.enemy)?;
 
-        Ok(Self { chars, enemy })
+        let mut state = Self {
+            chars,
+            enemy,
+            elapsed_frames: 0,
+            seed: args.seed,
+        };
+
+        let selectable = state.enemy.selectable_action_patterns(&state);
+        state.enemy.skill_runner_fill_schedule(&selectable);
+
+        Ok(state)
+    }
+
+    pub(crate) fn increment_frame(&mut self) {
+        self.elapsed_frames += 1;
     }
 
     pub(crate) fn tick(&self, effects_buffer: &mut EffectsBuffer) {
+        if self.elapsed_frames == 0 {
+            effects_buffer.push(Effect::BattleStart);
+        }
         for char in self.chars.iter() {
             char.tick(self, effects_buffer);
         }
@@ -53,9 +82,13 @@ impl GameState {
     }
 
     pub(crate) fn accept_effect(&mut self, effect: &Effect) {
+        let frame = self.elapsed_frames;
         match effect {
             Effect::Damage(damage) => {
                 self.get_lt_mut(damage.target()).accept_damage(damage.dmg());
+                if let (LtId::Enemy, DamageCauser::Char(_)) = (damage.target(), damage.causer()) {
+                    self.enemy.add_stagger(damage.dmg());
+                }
             }
             Effect::HealHp { target_id, num } => {
                 self.get_lt_mut(*target_id).accept_heal(*num);
@@ -63,6 +96,24 @@ impl GameState {
             Effect::ConsumeMp { target_id, num } => {
                 self.get_lt_mut(*target_id).accept_consume_mp(*num);
             }
+            Effect::ConsumeHp { target_id, num } => {
+                self.get_lt_mut(*target_id).accept_consume_hp(*num);
+            }
+            Effect::AddResource {
+                target_id,
+                key,
+                num,
+            } => {
+                self.get_lt_mut(*target_id).accept_add_resource(key, *num);
+            }
+            Effect::ConsumeResource {
+                target_id,
+                key,
+                num,
+            } => {
+                self.get_lt_mut(*target_id)
+                    .accept_consume_resource(key, *num);
+            }
             Effect::HealMp { target_id, num } => {
                 self.get_lt_mut(*target_id).accept_heal_mp(*num);
             }
@@ -75,8 +126,69 @@ impl GameState {
                     .passive
                     .update(*passive_id, message);
             }
-            Effect::AddPassive { target_id, passive } => {
-                self.get_lt_mut(*target_id).passive.add(passive.clone());
+            Effect::AddPassive {
+                target_id,
+                passive,
+                source,
+            } => {
+                self.get_lt_mut(*target_id).passive.add_with_source(
+                    passive.clone(),
+                    *source,
+                    frame,
+                );
+            }
+            Effect::UpdateConditionalPassiveStatus { target_id, status } => {
+                self.get_lt_mut(*target_id)
+                    .passive
+                    .set_conditional_status((**status).clone());
+            }
+            Effect::RemovePassive {
+                target_id,
+                passive_id,
+            } => {
+                self.get_lt_mut(*target_id).passive.remove(*passive_id);
+            }
+            Effect::DispelPassives {
+                target_id,
+                filter,
+                num,
+            } => {
+                self.get_lt_mut(*target_id).passive.dispel(*filter, *num);
+            }
+            Effect::ExpirePassive {
+                target_id,
+                passive_id,
+            } => {
+                self.get_lt_mut(*target_id)
+                    .passive
+                    .remove_expired(*passive_id);
+            }
+            Effect::DecrementPassiveRemainingFrames { target_id } => {
+                self.get_lt_mut(*target_id)
+                    .passive
+                    .decrement_remaining_frames(frame);
+            }
+            Effect::IncrementPassiveElapsedFrames { target_id } => {
+                self.get_lt_mut(*target_id)
+                    .passive
+                    .increment_elapsed_frames();
+            }
+            Effect::RefreshPassiveDuration {
+                target_id,
+                passive_id,
+            } => {
+                self.get_lt_mut(*target_id)
+                    .passive
+                    .refresh_remaining_frames(*passive_id, frame);
+            }
+            Effect::ExtendPassiveDuration {
+                target_id,
+                passive_id,
+                frames,
+            } => {
+                self.get_lt_mut(*target_id)
+                    .passive
+                    .extend_remaining_frames(*passive_id, *frames);
             }
             Effect::AddSkillCooldown { skill_id, num } => {
                 self.get_char_mut(skill_id.char_id)
@@ -89,6 +201,18 @@ impl GameState {
             Effect::AddHate { target_id, num } => {
                 self.get_char_mut(*target_id).add_hate(*num);
             }
+            Effect::ReduceHate { target_id, num } => {
+                self.get_char_mut(*target_id).reduce_hate(*num);
+            }
+            Effect::MulHate { target_id, mag } => {
+                self.get_char_mut(*target_id).mul_hate(*mag);
+            }
+            Effect::TransferHate { from, to, per } => {
+                let num = self.get_char(*from).hate() * per.get();
+                self.get_char_mut(*from).reduce_hate(num);
+                // 移す分には獲得ヘイトの倍率をかけない
+                self.get_char_mut(*to).add_hate_raw(num);
+            }
             Effect::UpdateSkillState { skill_id, msg } => {
                 self.get_skill_mut(*skill_id).skill_box_mut().update(msg);
             }
@@ -101,11 +225,39 @@ impl GameState {
                 );
                 self.get_char_mut(skill_id.char_id).use_skill(*skill_id);
             }
+            Effect::InterruptSkill { skill_id } => {
+                self.get_char_mut(skill_id.char_id).end_skill(*skill_id);
+            }
             Effect::EndSkill { skill_id } => {
                 self.get_char_mut(skill_id.char_id).end_skill(*skill_id);
             }
+            Effect::EnemyBreak => {
+                self.enemy.start_broken();
+                let selectable = self.enemy.selectable_action_patterns(self);
+                self.enemy.skill_runner_fill_schedule(&selectable);
+            }
+            Effect::EnemyDecrementBrokenFrame => {
+                self.enemy.decrement_broken_frame();
+            }
+            Effect::EnemySkillRunnerFizzle => {
+                self.enemy.skill_runner_fizzle();
+            }
+            Effect::EnemyChangePhase { phase } => {
+                if self.enemy.change_phase(*phase, frame) {
+                    let selectable = self.enemy.selectable_action_patterns(self);
+                    self.enemy.skill_runner_flush_schedule(&selectable);
+                }
+            }
+            // パッシブの発動のためだけに使う
+            Effect::BattleStart | Effect::EnemySkillFired { .. } => {}
+            Effect::EnemyAddFrameProgress { num } => {
+                self.enemy.add_frame_progress(*num);
+            }
             Effect::EnemySkillRunnerIncrementFrame => {
-                self.enemy.skill_runner_increment_frame();
+                if self.enemy.skill_runner_increment_frame() {
+                    let selectable = self.enemy.selectable_action_patterns(self);
+                    self.enemy.skill_runner_fill_schedule(&selectable);
+                }
             }
         }
     }
@@ -144,6 +296,14 @@ impl GameState {
         self.get_char_mut(id.char_id).get_skill_mut(id)
     }
 
+    /// 全てのキャラクターと敵
+    pub fn get_lts(&self) -> impl Iterator<Item = (LtId, &LtCommon)> {
+        self.chars
+            .iter()
+            .map(|c| (c.lt_id(), c.lt()))
+            .chain(std::iter::once((LtId::Enemy, self.enemy.lt())))
+    }
+
     pub fn get_lt(&self, id: LtId) -> &LtCommon {
         match id {
             LtId::Enemy => self.enemy.lt(),
@@ -167,6 +327,28 @@ impl GameState {
         &self.chars
     }
 
+    pub fn num_alive_chars(&self) -> usize {
+        self.chars.iter().filter(|c| !c.lt().is_dead()).count()
+    }
+
+    /// 戦闘開始からの経過フレーム数
+    pub fn elapsed_frames(&self) -> u64 {
+        self.elapsed_frames
+    }
+
+    /// シードと経過フレームと用途ごとのsaltから決まる乱数生成器
+    ///
+    /// 同じフレーム、同じsaltであれば何度呼んでも同じ乱数列になる。
+    /// 予測と実際の結果を一致させたい場合などに使う。
+    /// 用途が異なる場合は異なるsaltを渡して乱数列を分ける。
+    pub fn frame_rng(&self, salt: u64) -> StdRng {
+        StdRng::seed_from_u64(
+            self.seed
+                ^ self.elapsed_frames.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+                ^ salt.wrapping_mul(0xBF58_476D_1CE4_E5B9),
+        )
+    }
+
     pub fn get_highest_hate_char(&self) -> &ButtleChar {
         assert!(!self.chars.is_empty());
 
@@ -177,6 +359,14 @@ impl GameState {
             .unwrap()
     }
 
+    /// 敵の攻撃対象になる優先順
+    /// 挑発中のキャラクターが先頭になり、それぞれの中ではヘイトの降順
+    pub fn get_chars_sorted_by_target_priority(&self) -> Vec<&ButtleChar> {
+        let mut tmp = self.get_chars_sorted_by_hate();
+        tmp.sort_by_key(|c| !c.lt().is_taunting());
+        tmp
+    }
+
     pub fn get_chars_sorted_by_hate(&self) -> Vec<&ButtleChar> {
         let mut tmp = self.chars.iter().collect::<Vec<_>>();
         tmp.sort_by(|a, b| b.hate().total_cmp(&a.hate()));
@@ -8,12 +8,14 @@ pub mod buttle_enemy;
 pub mod buttle_skill;
 pub mod damage;
 pub mod effect;
+pub mod enemy_action_pattern;
 pub mod enemy_skill;
 pub mod game_state;
 pub mod lt_common;
 pub mod passive;
 pub mod potential;
 pub mod progress_state;
+pub mod resource;
 pub mod skill;
 pub mod skill_impl_utils;
 pub mod weapon;
@@ -27,6 +29,8 @@ pub type StaticSkillId = u32;
 pub type StaticPassiveId = u32;
 pub type StatusNum = f32;
 pub type TimeNum = f32;
+/// 1秒あたりのフレーム数
+pub const FPS: TimeNum = 100.0;
 pub type LevelNum = u32;
 
 pub const MAX_CHARACTERS: usize = 4;
@@ -1,7 +1,17 @@
+use std::collections::HashMap;
+
 use crate::{
-    LevelNum, StatusNum, TimeNum, core_actor::EffectsBuffer, effect::Effect, game_state::GameState,
-    lt_common::any_point::AnyPointPercent, passive::PassiveList, potential::Potential,
-    runtime_id::LtId, weapon::Weapon,
+    FPS, LevelNum, StatusNum, TimeNum,
+    core_actor::EffectsBuffer,
+    effect::Effect,
+    game_state::GameState,
+    lt_common::any_point::AnyPointPercent,
+    passive::{PassiveInfomation, PassiveList, PassiveTag},
+    potential::Potential,
+    resource::{Resource, ResourceBar, ResourceGeneration, ResourceKey},
+    runtime_id::LtId,
+    skill::CharSkillProgressKind,
+    weapon::Weapon,
 };
 
 mod any_point;
@@ -14,6 +24,10 @@ pub struct LtCommon {
     hp_per: AnyPointPercent,
     mp_per: AnyPointPercent,
     weapon: Option<Weapon>,
+    /// タグごとのパッシブへの耐性 (0.0 ~ 1.0)。1.0の場合は無効
+    resistances: HashMap<PassiveTag, StatusNum>,
+    /// HP/MP以外のリソース。宣言された順
+    resources: Vec<Resource>,
 }
 
 impl LtCommon {
@@ -25,6 +39,8 @@ impl LtCommon {
             mp_per: AnyPointPercent::new(0.5),
             passive: PassiveList::default(),
             weapon,
+            resistances: HashMap::new(),
+            resources: Vec::new(),
         }
     }
 
@@ -46,6 +62,46 @@ impl LtCommon {
             target_id: owner_id,
             num: self.mp_heal(),
         });
+
+        self.generate_resources(owner_id, effects_buffer, |g| match g {
+            ResourceGeneration::PerSec(num) => Some(num / FPS),
+            _ => None,
+        });
+    }
+
+    /// 条件に合うリソースの獲得量を求めてAddResourceを追加する
+    /// 最大値に達している場合は何もしない
+    pub(crate) fn generate_resources(
+        &self,
+        owner_id: LtId,
+        effects_buffer: &mut EffectsBuffer,
+        amount: impl Fn(ResourceGeneration) -> Option<StatusNum>,
+    ) {
+        self.resources
+            .iter()
+            .filter(|r| r.value < r.bar.max)
+            .for_each(|r| {
+                let num: StatusNum = r.bar.generation.iter().filter_map(|g| amount(*g)).sum();
+                if num > 0.0 {
+                    effects_buffer.push(Effect::AddResource {
+                        target_id: owner_id,
+                        key: r.bar.key,
+                        num,
+                    });
+                }
+            });
+    }
+
+    pub(crate) fn add_resource_bar(&mut self, bar: ResourceBar) -> Result<(), crate::Error> {
+        bar.validate()?;
+        if self.resources.iter().any(|r| r.bar.key == bar.key) {
+            return Err(crate::Error::InvalidArgument(format!(
+                "Duplicate resource: {}",
+                bar.key
+            )));
+        }
+        self.resources.push(Resource::new(bar));
+        Ok(())
     }
 }
 
@@ -118,6 +174,12 @@ impl LtCommon {
         self.hp_per.get(self.max_hp())
     }
 
+    /// 最大HPに対する現在のHPの割合
+    /// HPが0を下回っている場合は負の値になる
+    pub fn hp_per(&self) -> StatusNum {
+        self.hp() / self.max_hp()
+    }
+
     pub fn recv_magic_dmg_mag(&self) -> StatusNum {
         self.passive.status().recv_magic_dmg_mag.get()
     }
@@ -134,6 +196,8 @@ impl LtCommon {
         let base = (self.vit() * 2.0 + self.dex() + self.int()) / 4.0;
         let mp_scale = 50.0;
         base * mp_scale
+            * self.passive.status().max_mp_mag_buff.get()
+            * self.passive.status().max_mp_mag_debuff.get()
     }
 
     pub fn mp_heal(&self) -> StatusNum {
@@ -142,7 +206,6 @@ impl LtCommon {
         // potentialが0の時に1sあたりMPがBASE回復する
         const M: f32 = 10.0;
         const N: f32 = 7.0;
-        const FPS: f32 = 100.0;
         const MP_HEAL_BASE_PER_SEC: f32 = 1.0;
 
         // 調整した時のために残しておきたい
@@ -175,9 +238,15 @@ impl LtCommon {
             diff < 0.00001
         });
 
-        let mp_heal = mp_heal_f(potential);
+        let mp_heal = mp_heal_f(potential)
+            * self.passive.status().mp_heal_mag_buff.get()
+            * self.passive.status().mp_heal_mag_debuff.get();
 
-        debug_assert!(mp_heal > 0.0, "mp_heal should be positive: {}", mp_heal);
+        debug_assert!(
+            mp_heal >= 0.0,
+            "mp_heal should be non-negative: {}",
+            mp_heal
+        );
 
         mp_heal
     }
@@ -185,6 +254,118 @@ impl LtCommon {
     pub fn speed(&self) -> TimeNum {
         // potentialが10のとき、1フレームあたり10進める。
         (self.agi() * 4.0 + self.dex() + self.str()) / 6.0
+            * self.passive.status().speed_mag_buff.get()
+            * self.passive.status().speed_mag_debuff.get()
+            * self.slow_mag()
+    }
+
+    /// 詠唱の進行速度
+    pub fn chant_speed(&self) -> TimeNum {
+        self.speed()
+            * self.passive.status().chant_speed_mag_buff.get()
+            * self.passive.status().chant_speed_mag_debuff.get()
+    }
+
+    /// 詠唱以外の行動の進行速度
+    pub fn action_speed(&self) -> TimeNum {
+        self.speed()
+            * self.passive.status().action_speed_mag_buff.get()
+            * self.passive.status().action_speed_mag_debuff.get()
+    }
+
+    /// スキルの進行の種類ごとの速度
+    pub fn skill_progress_speed(&self, kind: CharSkillProgressKind) -> TimeNum {
+        match kind {
+            CharSkillProgressKind::Chanting => self.chant_speed(),
+            CharSkillProgressKind::Acting => self.action_speed(),
+        }
+    }
+
+    /// 鈍足による速度の倍率
+    pub fn slow_mag(&self) -> StatusNum {
+        self.passive.status().slow.get()
+    }
+
+    pub fn is_stunned(&self) -> bool {
+        self.passive.status().stun
+    }
+
+    pub fn is_uninterruptible(&self) -> bool {
+        self.passive.status().uninterruptible
+    }
+
+    pub fn is_silenced(&self) -> bool {
+        self.passive.status().silence
+    }
+
+    /// クールダウンの回復速度の倍率
+    pub fn cooldown_heal_mag(&self) -> TimeNum {
+        self.passive.status().cooldown_heal_mag_buff.get()
+            * self.passive.status().cooldown_heal_mag_debuff.get()
+    }
+
+    /// 消費MPの倍率
+    pub fn mp_cost_mag(&self) -> StatusNum {
+        let efficiency = self.passive.status().mp_efficiency_buff.get()
+            * self.passive.status().mp_efficiency_debuff.get();
+        if efficiency <= 0.0 {
+            // 効率が0の場合は使用できないようにする
+            StatusNum::INFINITY
+        } else {
+            1.0 / efficiency
+        }
+    }
+
+    /// 受けるHP回復量の倍率
+    pub fn recv_heal_mag(&self) -> StatusNum {
+        self.passive.status().recv_heal_mag_buff.get()
+            * self.passive.status().recv_heal_mag_debuff.get()
+    }
+
+    /// 与えるHP回復量の倍率
+    pub fn deal_heal_mag(&self) -> StatusNum {
+        self.passive.status().deal_heal_mag_buff.get()
+            * self.passive.status().deal_heal_mag_debuff.get()
+    }
+
+    /// 獲得ヘイトの倍率
+    pub fn hate_mag(&self) -> StatusNum {
+        self.passive.status().hate_mag_buff.get() * self.passive.status().hate_mag_debuff.get()
+    }
+
+    pub fn is_taunting(&self) -> bool {
+        self.passive.status().taunt
+    }
+
+    /// タグに対する耐性 (0.0 ~ 1.0)
+    pub fn resistance(&self, tag: PassiveTag) -> StatusNum {
+        self.resistances.get(tag).copied().unwrap_or(0.0)
+    }
+
+    /// パッシブのタグの中で最も高い耐性
+    pub fn passive_resistance(&self, info: &PassiveInfomation) -> StatusNum {
+        info.tags
+            .iter()
+            .map(|tag| self.resistance(tag))
+            .fold(0.0, StatusNum::max)
+    }
+
+    pub(crate) fn set_resistance(&mut self, tag: PassiveTag, chance: StatusNum) {
+        assert!((0.0..=1.0).contains(&chance));
+        self.resistances.insert(tag, chance);
+    }
+
+    /// 持っていないリソースの場合は0
+    pub fn resource(&self, key: ResourceKey) -> StatusNum {
+        self.resources
+            .iter()
+            .find(|r| r.bar.key == key)
+            .map_or(0.0, |r| r.value)
+    }
+
+    /// 持っているリソースの定義と現在値。宣言された順
+    pub fn resources(&self) -> impl Iterator<Item = (&ResourceBar, StatusNum)> {
+        self.resources.iter().map(|r| (&r.bar, r.value))
     }
 
     pub fn is_dead(&self) -> bool {
@@ -198,6 +379,11 @@ impl LtCommon {
         self.hp_per.add(self.max_hp(), -dmg);
     }
 
+    /// スキルのコストなどで直接HPを減らす。ダメージとしては扱わない
+    pub(crate) fn accept_consume_hp(&mut self, num: StatusNum) {
+        self.hp_per.add(self.max_hp(), -num);
+    }
+
     pub(crate) fn accept_heal(&mut self, heal: StatusNum) {
         self.hp_per.add(self.max_hp(), heal);
     }
@@ -209,4 +395,15 @@ impl LtCommon {
     pub(crate) fn accept_heal_mp(&mut self, num: StatusNum) {
         self.mp_per.add(self.max_mp(), num);
     }
+
+    /// 持っていないリソースの場合は何もしない
+    pub(crate) fn accept_add_resource(&mut self, key: ResourceKey, num: StatusNum) {
+        if let Some(r) = self.resources.iter_mut().find(|r| r.bar.key == key) {
+            r.add(num);
+        }
+    }
+
+    pub(crate) fn accept_consume_resource(&mut self, key: ResourceKey, num: StatusNum) {
+        self.accept_add_resource(key, -num);
+    }
 }
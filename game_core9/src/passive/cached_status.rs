@@ -1,6 +1,6 @@
 use std::cell::{Ref, RefCell};
 
-use crate::passive::{PassiveBox, PassiveStatus};
+use crate::passive::{PassiveStatus, entry::PassiveEntry};
 
 #[derive(Debug, Clone)]
 pub(super) struct CachedPassiveStatus {
@@ -22,14 +22,19 @@ impl CachedPassiveStatus {
 
     pub fn get<'a>(
         &self,
-        passives: impl Iterator<Item = &'a PassiveBox>,
+        passives: impl Iterator<Item = &'a PassiveEntry>,
+        conditional: &PassiveStatus,
     ) -> Ref<'_, PassiveStatus> {
         if *self.need_update.borrow() {
             let mut cache = self.cache.borrow_mut();
             cache.reset();
-            passives.for_each(|item| {
-                item.status(&mut cache);
+            // スタック数の回数だけ呼ぶ
+            passives.for_each(|entry| {
+                entry.instances.iter().for_each(|i| {
+                    (0..entry.stacks).for_each(|_| i.passive.status(&mut cache));
+                });
             });
+            cache.merge(conditional);
             *self.need_update.borrow_mut() = false
         };
         self.cache.borrow()
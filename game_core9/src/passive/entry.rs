@@ -0,0 +1,187 @@
+use crate::{
+    any_message::AnyMessageBox,
+    passive::{PassiveBox, stack_policy::PassiveStackPolicy},
+    runtime_id::LtId,
+};
+
+#[derive(Debug, Clone)]
+pub(super) struct PassiveInstance {
+    pub passive: PassiveBox,
+    /// 付与元。初期パッシブなど付与元がない場合はNone
+    pub source: Option<LtId>,
+    /// 効果時間が設定されていない場合はNone
+    pub remaining_frames: Option<u64>,
+    /// 付与されてからの経過フレーム。PassiveTrait::intervalの判定に使う
+    pub elapsed_frames: u64,
+    /// 付与または効果時間が更新されたフレーム。このフレームでは残り時間を減らさない
+    pub applied_frame: u64,
+}
+
+impl PassiveInstance {
+    fn new(passive: PassiveBox, source: Option<LtId>, frame: u64) -> Self {
+        let remaining_frames = passive.duration();
+        Self {
+            passive,
+            source,
+            remaining_frames,
+            elapsed_frames: 0,
+            applied_frame: frame,
+        }
+    }
+
+    fn refresh(&mut self, frame: u64) {
+        self.remaining_frames = self.passive.duration();
+        self.applied_frame = frame;
+    }
+}
+
+/// 同じIDのパッシブをまとめたもの
+/// IndependentPerSource以外の場合、instancesは常に1つ
+#[derive(Debug, Clone)]
+pub(super) struct PassiveEntry {
+    pub instances: Vec<PassiveInstance>,
+    pub stacks: u32,
+}
+
+impl PassiveEntry {
+    pub fn new(passive: PassiveBox, source: Option<LtId>, frame: u64) -> Self {
+        Self {
+            instances: vec![PassiveInstance::new(passive, source, frame)],
+            stacks: 1,
+        }
+    }
+
+    /// 代表のパッシブ
+    pub fn first(&self) -> &PassiveBox {
+        &self.instances.first().unwrap().passive
+    }
+
+    pub fn policy(&self) -> PassiveStackPolicy {
+        self.first().stack_policy()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    pub fn add(&mut self, passive: PassiveBox, source: Option<LtId>, frame: u64) {
+        match self.policy() {
+            PassiveStackPolicy::Merge => {
+                let instance = self.instances.first_mut().unwrap();
+                instance.passive.merge(&passive);
+                if let Some(duration) = passive.duration() {
+                    let remaining = instance.remaining_frames.get_or_insert(0);
+                    *remaining = (*remaining).max(duration);
+                    instance.applied_frame = frame;
+                }
+            }
+            PassiveStackPolicy::Replace => {
+                self.instances = vec![PassiveInstance::new(passive, source, frame)];
+            }
+            PassiveStackPolicy::RefreshDuration => {
+                self.instances.first_mut().unwrap().refresh(frame);
+            }
+            PassiveStackPolicy::Stack { max } => {
+                self.stacks = (self.stacks + 1).min(max);
+                self.instances.first_mut().unwrap().refresh(frame);
+            }
+            PassiveStackPolicy::KeepStrongest => {
+                if passive.strength() >= self.first().strength() {
+                    self.instances = vec![PassiveInstance::new(passive, source, frame)];
+                }
+            }
+            PassiveStackPolicy::IndependentPerSource => {
+                let new = PassiveInstance::new(passive, source, frame);
+                match self.instances.iter_mut().find(|i| i.source == source) {
+                    Some(instance) => *instance = new,
+                    None => self.instances.push(new),
+                }
+            }
+        }
+        self.remove_trash();
+    }
+
+    pub fn update(&mut self, msg: &AnyMessageBox) {
+        self.instances
+            .iter_mut()
+            .for_each(|i| i.passive.update(msg));
+        self.remove_trash();
+    }
+
+    /// 残り時間が最も長いもの
+    pub fn remaining_frames(&self) -> Option<u64> {
+        self.instances
+            .iter()
+            .filter_map(|i| i.remaining_frames)
+            .max()
+    }
+
+    /// 経過フレームがintervalの倍数になったもの。スタック数の分だけ繰り返す
+    pub fn interval_instances(&self) -> impl Iterator<Item = &PassiveBox> {
+        self.instances
+            .iter()
+            .filter(|i| {
+                i.passive.interval().is_some_and(|interval| {
+                    assert!(interval > 0);
+                    i.elapsed_frames.is_multiple_of(interval)
+                })
+            })
+            .flat_map(|i| std::iter::repeat_n(&i.passive, self.stacks as usize))
+    }
+
+    pub fn has_interval(&self) -> bool {
+        self.instances
+            .iter()
+            .any(|i| i.passive.interval().is_some())
+    }
+
+    pub fn increment_elapsed_frames(&mut self) {
+        self.instances
+            .iter_mut()
+            .for_each(|i| i.elapsed_frames += 1);
+    }
+
+    pub fn has_duration(&self) -> bool {
+        self.instances.iter().any(|i| i.remaining_frames.is_some())
+    }
+
+    pub fn is_expiring(&self) -> bool {
+        self.instances
+            .iter()
+            .any(|i| i.remaining_frames.is_some_and(|r| r <= 1))
+    }
+
+    /// frameに付与されたものは減らさない
+    pub fn decrement_remaining_frames(&mut self, frame: u64) {
+        self.instances.iter_mut().for_each(|i| {
+            if i.applied_frame == frame {
+                return;
+            }
+            if let Some(remaining) = i.remaining_frames.as_mut() {
+                *remaining = remaining.saturating_sub(1);
+            }
+        });
+    }
+
+    /// 残り時間が切れたものを取り除く
+    pub fn remove_expired(&mut self) {
+        self.instances
+            .retain(|i| i.remaining_frames.is_none_or(|r| r > 1));
+    }
+
+    pub fn refresh(&mut self, frame: u64) {
+        self.instances.iter_mut().for_each(|i| i.refresh(frame));
+    }
+
+    pub fn extend(&mut self, frames: u64) {
+        self.instances.iter_mut().for_each(|i| {
+            if let Some(remaining) = i.remaining_frames.as_mut() {
+                *remaining += frames;
+            }
+        });
+    }
+
+    fn remove_trash(&mut self) {
+        self.instances.retain(|i| !i.passive.should_trash());
+    }
+}
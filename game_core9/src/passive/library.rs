@@ -0,0 +1,435 @@
+//! よく使うパッシブの実装
+//!
+//! IDや名前はPassiveInfomationとして外から渡す。
+//! 同じIDのパッシブが追加された場合の挙動はstack_policyで指定する。
+
+use crate::{
+    StatusNum,
+    any_message::AnyMessageBox,
+    core_actor::EffectsBuffer,
+    damage::{Damage, DamageType, PendingDamage},
+    effect::Effect,
+    game_state::GameState,
+    passive::{
+        AuraTarget, PassiveBox, PassiveInfomation, PassiveTrait, stack_policy::PassiveStackPolicy,
+        status::PassiveStatus,
+    },
+    runtime_id::LtId,
+};
+
+/// ステータスを変更する
+#[derive(Debug, Clone)]
+pub struct StatusModifier {
+    pub info: &'static PassiveInfomation,
+    /// Noneの場合は永続
+    pub duration: Option<u64>,
+    pub stack_policy: PassiveStackPolicy,
+    pub modify: fn(&mut PassiveStatus),
+}
+
+impl PassiveTrait for StatusModifier {
+    fn info(&self) -> &PassiveInfomation {
+        self.info
+    }
+    fn summary(&self) -> String {
+        let mut status = PassiveStatus::default();
+        (self.modify)(&mut status);
+        status.summary()
+    }
+    fn should_trash(&self) -> bool {
+        false
+    }
+    fn merge(&mut self, _passive: &PassiveBox) {}
+    fn tick(&self, _owner: LtId, _state: &GameState, _effects_buffer: &mut EffectsBuffer) {}
+    fn update(&mut self, _msg: &AnyMessageBox) {}
+    fn status(&self, status: &mut PassiveStatus) {
+        (self.modify)(status);
+    }
+    fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+    fn stack_policy(&self) -> PassiveStackPolicy {
+        self.stack_policy
+    }
+}
+
+/// 条件を満たしている間だけステータスを変更する
+/// HPが減ると攻撃力が上がる、味方の生存数に応じて強くなるなど
+#[derive(Debug, Clone)]
+pub struct ConditionalStatusModifier {
+    pub info: &'static PassiveInfomation,
+    pub duration: Option<u64>,
+    pub stack_policy: PassiveStackPolicy,
+    /// 所有者と状態から、modifyを何回適用するかを返す
+    pub count: fn(LtId, &GameState) -> u32,
+    pub modify: fn(&mut PassiveStatus),
+}
+
+impl PassiveTrait for ConditionalStatusModifier {
+    fn info(&self) -> &PassiveInfomation {
+        self.info
+    }
+    fn should_trash(&self) -> bool {
+        false
+    }
+    fn merge(&mut self, _passive: &PassiveBox) {}
+    fn tick(&self, _owner: LtId, _state: &GameState, _effects_buffer: &mut EffectsBuffer) {}
+    fn update(&mut self, _msg: &AnyMessageBox) {}
+    fn conditional_status(&self, owner: LtId, state: &GameState, status: &mut PassiveStatus) {
+        (0..(self.count)(owner, state)).for_each(|_| (self.modify)(status));
+    }
+    fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+    fn stack_policy(&self) -> PassiveStackPolicy {
+        self.stack_policy
+    }
+}
+
+/// 所有者が生きている間、味方全員または敵全員のステータスを変更する
+#[derive(Debug, Clone)]
+pub struct Aura {
+    pub info: &'static PassiveInfomation,
+    pub duration: Option<u64>,
+    pub stack_policy: PassiveStackPolicy,
+    pub target: AuraTarget,
+    pub modify: fn(&mut PassiveStatus),
+}
+
+impl PassiveTrait for Aura {
+    fn info(&self) -> &PassiveInfomation {
+        self.info
+    }
+    fn summary(&self) -> String {
+        let mut status = PassiveStatus::default();
+        (self.modify)(&mut status);
+        let target = match self.target {
+            AuraTarget::Allies => "味方",
+            AuraTarget::Enemies => "敵",
+        };
+        format!("{target}全体 {}", status.summary())
+    }
+    fn should_trash(&self) -> bool {
+        false
+    }
+    fn merge(&mut self, _passive: &PassiveBox) {}
+    fn tick(&self, _owner: LtId, _state: &GameState, _effects_buffer: &mut EffectsBuffer) {}
+    fn update(&mut self, _msg: &AnyMessageBox) {}
+    fn aura_target(&self) -> Option<AuraTarget> {
+        Some(self.target)
+    }
+    fn aura_status(&self, status: &mut PassiveStatus) {
+        (self.modify)(status);
+    }
+    fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+    fn stack_policy(&self) -> PassiveStackPolicy {
+        self.stack_policy
+    }
+}
+
+/// 毒や火傷などの継続ダメージ
+#[derive(Debug, Clone)]
+pub struct DamageOverTime {
+    pub info: &'static PassiveInfomation,
+    pub duration: Option<u64>,
+    pub stack_policy: PassiveStackPolicy,
+    /// ダメージを与える間隔(フレーム)。付与されてから数える
+    pub interval: u64,
+    /// 1回あたりの固定ダメージ
+    pub dmg: StatusNum,
+    /// 付与した者。与ダメージ時のパッシブの発動に使う
+    pub causer: Option<LtId>,
+}
+
+impl PassiveTrait for DamageOverTime {
+    fn info(&self) -> &PassiveInfomation {
+        self.info
+    }
+    fn summary(&self) -> String {
+        format!("{}ダメージ", self.dmg)
+    }
+    fn should_trash(&self) -> bool {
+        false
+    }
+    fn merge(&mut self, _passive: &PassiveBox) {}
+    fn tick(&self, _owner: LtId, _state: &GameState, _effects_buffer: &mut EffectsBuffer) {}
+    fn update(&mut self, _msg: &AnyMessageBox) {}
+    fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+    fn interval(&self) -> Option<u64> {
+        Some(self.interval)
+    }
+    fn trigger_interval(
+        &self,
+        owner: LtId,
+        _state: &GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+        effects_buffer.push(Effect::Damage(Damage::new_fixed_damage(
+            self.causer,
+            owner,
+            self.dmg,
+        )));
+    }
+    fn stack_policy(&self) -> PassiveStackPolicy {
+        self.stack_policy
+    }
+    fn strength(&self) -> StatusNum {
+        self.dmg
+    }
+}
+
+/// 継続的なHP回復
+#[derive(Debug, Clone)]
+pub struct HealOverTime {
+    pub info: &'static PassiveInfomation,
+    pub duration: Option<u64>,
+    pub stack_policy: PassiveStackPolicy,
+    /// 回復する間隔(フレーム)。付与されてから数える
+    pub interval: u64,
+    pub num: StatusNum,
+    /// 付与した者。与える回復量の倍率に使う
+    pub healer: Option<LtId>,
+}
+
+impl PassiveTrait for HealOverTime {
+    fn info(&self) -> &PassiveInfomation {
+        self.info
+    }
+    fn summary(&self) -> String {
+        format!("HP{}回復", self.num)
+    }
+    fn should_trash(&self) -> bool {
+        false
+    }
+    fn merge(&mut self, _passive: &PassiveBox) {}
+    fn tick(&self, _owner: LtId, _state: &GameState, _effects_buffer: &mut EffectsBuffer) {}
+    fn update(&mut self, _msg: &AnyMessageBox) {}
+    fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+    fn interval(&self) -> Option<u64> {
+        Some(self.interval)
+    }
+    fn trigger_interval(&self, owner: LtId, state: &GameState, effects_buffer: &mut EffectsBuffer) {
+        effects_buffer.push(Effect::new_heal_hp(state, self.healer, owner, self.num));
+    }
+    fn stack_policy(&self) -> PassiveStackPolicy {
+        self.stack_policy
+    }
+    fn strength(&self) -> StatusNum {
+        self.num
+    }
+}
+
+/// 継続的なMP回復
+#[derive(Debug, Clone)]
+pub struct MpRegen {
+    pub info: &'static PassiveInfomation,
+    pub duration: Option<u64>,
+    pub stack_policy: PassiveStackPolicy,
+    /// 回復する間隔(フレーム)。付与されてから数える
+    pub interval: u64,
+    pub num: StatusNum,
+}
+
+impl PassiveTrait for MpRegen {
+    fn info(&self) -> &PassiveInfomation {
+        self.info
+    }
+    fn summary(&self) -> String {
+        format!("MP{}回復", self.num)
+    }
+    fn should_trash(&self) -> bool {
+        false
+    }
+    fn merge(&mut self, _passive: &PassiveBox) {}
+    fn tick(&self, _owner: LtId, _state: &GameState, _effects_buffer: &mut EffectsBuffer) {}
+    fn update(&mut self, _msg: &AnyMessageBox) {}
+    fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+    fn interval(&self) -> Option<u64> {
+        Some(self.interval)
+    }
+    fn trigger_interval(
+        &self,
+        owner: LtId,
+        _state: &GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+        effects_buffer.push(Effect::HealMp {
+            target_id: owner,
+            num: self.num,
+        });
+    }
+    fn stack_policy(&self) -> PassiveStackPolicy {
+        self.stack_policy
+    }
+    fn strength(&self) -> StatusNum {
+        self.num
+    }
+}
+
+/// 受けるダメージを適用前に減らす
+#[derive(Debug, Clone)]
+pub struct DamageReduction {
+    info: &'static PassiveInfomation,
+    duration: Option<u64>,
+    stack_policy: PassiveStackPolicy,
+    /// 受けるダメージに乗算させる値 (0.0 ~ 1.0)
+    mag: StatusNum,
+    /// Noneの場合は全ての種類のダメージを減らす
+    ty: Option<DamageType>,
+}
+
+impl DamageReduction {
+    /// magは受けるダメージに乗算させる値 (0.0 ~ 1.0)
+    /// tyがNoneの場合は全ての種類のダメージを減らす
+    pub fn new(
+        info: &'static PassiveInfomation,
+        duration: Option<u64>,
+        stack_policy: PassiveStackPolicy,
+        mag: StatusNum,
+        ty: Option<DamageType>,
+    ) -> Result<Self, crate::Error> {
+        if !(0.0..=1.0).contains(&mag) {
+            return Err(crate::Error::InvalidArgument(format!(
+                "DamageReduction mag must be in 0.0..=1.0: {}",
+                mag
+            )));
+        }
+        Ok(Self {
+            info,
+            duration,
+            stack_policy,
+            mag,
+            ty,
+        })
+    }
+}
+
+impl PassiveTrait for DamageReduction {
+    fn info(&self) -> &PassiveInfomation {
+        self.info
+    }
+    fn summary(&self) -> String {
+        format!("被ダメージ{}%", self.mag * 100.0)
+    }
+    fn should_trash(&self) -> bool {
+        false
+    }
+    fn merge(&mut self, _passive: &PassiveBox) {}
+    fn tick(&self, _owner: LtId, _state: &GameState, _effects_buffer: &mut EffectsBuffer) {}
+    fn update(&mut self, _msg: &AnyMessageBox) {}
+    fn modify_recv_damage(&self, _owner: LtId, dmg: &mut PendingDamage, _state: &GameState) {
+        if self.ty.is_none_or(|ty| ty == dmg.damage().ty()) {
+            dmg.mul(self.mag);
+        }
+    }
+    fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+    fn stack_policy(&self) -> PassiveStackPolicy {
+        self.stack_policy
+    }
+    /// 減らす割合が大きいほど強い
+    fn strength(&self) -> StatusNum {
+        1.0 - self.mag
+    }
+}
+
+/// 受けたダメージの一部を攻撃した者に返す
+///
+/// 返すダメージは固定ダメージで、固定ダメージは返さない。
+/// そのため反射同士で無限に返し合うことはない。
+#[derive(Debug, Clone)]
+pub struct Thorns {
+    pub info: &'static PassiveInfomation,
+    pub duration: Option<u64>,
+    pub stack_policy: PassiveStackPolicy,
+    /// 受けたダメージに対する返すダメージの割合
+    pub per: StatusNum,
+}
+
+impl PassiveTrait for Thorns {
+    fn info(&self) -> &PassiveInfomation {
+        self.info
+    }
+    fn summary(&self) -> String {
+        format!("反射{}%", self.per * 100.0)
+    }
+    fn should_trash(&self) -> bool {
+        false
+    }
+    fn merge(&mut self, _passive: &PassiveBox) {}
+    fn tick(&self, _owner: LtId, _state: &GameState, _effects_buffer: &mut EffectsBuffer) {}
+    fn update(&mut self, _msg: &AnyMessageBox) {}
+    fn trigger_recv_damage(
+        &self,
+        owner: LtId,
+        dmg: &Damage,
+        _state: &GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+        if dmg.ty() == DamageType::Fixed {
+            return;
+        }
+        let Some(causer) = dmg.causer().to_lt_id() else {
+            return;
+        };
+        if causer == owner {
+            return;
+        }
+
+        effects_buffer.push(Effect::Damage(Damage::new_fixed_damage(
+            Some(owner),
+            causer,
+            dmg.dmg() * self.per,
+        )));
+    }
+    fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+    fn stack_policy(&self) -> PassiveStackPolicy {
+        self.stack_policy
+    }
+    fn strength(&self) -> StatusNum {
+        self.per
+    }
+}
+
+/// 敵の単体攻撃を引きつける
+#[derive(Debug, Clone)]
+pub struct Taunt {
+    pub info: &'static PassiveInfomation,
+    pub duration: Option<u64>,
+    pub stack_policy: PassiveStackPolicy,
+}
+
+impl PassiveTrait for Taunt {
+    fn info(&self) -> &PassiveInfomation {
+        self.info
+    }
+    fn summary(&self) -> String {
+        "挑発".to_string()
+    }
+    fn should_trash(&self) -> bool {
+        false
+    }
+    fn merge(&mut self, _passive: &PassiveBox) {}
+    fn tick(&self, _owner: LtId, _state: &GameState, _effects_buffer: &mut EffectsBuffer) {}
+    fn update(&mut self, _msg: &AnyMessageBox) {}
+    fn status(&self, status: &mut PassiveStatus) {
+        status.taunt = true;
+    }
+    fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+    fn stack_policy(&self) -> PassiveStackPolicy {
+        self.stack_policy
+    }
+}
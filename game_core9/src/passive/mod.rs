@@ -6,19 +6,26 @@ use std::{
 
 mod added_order;
 mod cached_status;
+mod entry;
+pub mod library;
+pub mod stack_policy;
 pub mod status;
 
 use downcast_rs::{Downcast, impl_downcast};
 use dyn_clone::DynClone;
 
 use crate::{
-    StaticPassiveId,
+    StaticEnemySkillId, StaticPassiveId, StatusNum,
     any_message::AnyMessageBox,
     core_actor::EffectsBuffer,
-    damage::Damage,
+    damage::{Damage, PendingDamage},
+    effect::Effect,
     game_state::GameState,
-    passive::{added_order::AddedOrder, status::PassiveStatus},
-    runtime_id::LtId,
+    passive::{
+        added_order::AddedOrder, entry::PassiveEntry, stack_policy::PassiveStackPolicy,
+        status::PassiveStatus,
+    },
+    runtime_id::{LtId, RuntimeSkillId},
 };
 
 //--------------------------------------------------//
@@ -52,10 +59,80 @@ impl DerefMut for PassiveBox {
 //                                                  //
 //--------------------------------------------------//
 
+#[derive(Debug)]
 pub struct PassiveInfomation {
     pub id: StaticPassiveId,
     pub name: &'static str,
+    /// UIでアイコンを探すためのキー
+    pub icon_key: &'static str,
     pub description: &'static str,
+    pub category: PassiveCategory,
+    /// 解除や耐性の対象を指定するためのタグ
+    pub tags: &'static [PassiveTag],
+}
+
+pub type PassiveTag = &'static str;
+
+/// UI表示用のパッシブの情報
+#[derive(Debug, Clone)]
+pub struct PassiveView {
+    pub id: StaticPassiveId,
+    pub name: &'static str,
+    pub icon_key: &'static str,
+    pub category: PassiveCategory,
+    pub stacks: u32,
+    /// 効果時間が設定されていない場合はNone
+    pub remaining_frames: Option<u64>,
+    /// 本来の効果時間
+    pub max_frames: Option<u64>,
+    pub summary: String,
+    pub description: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassiveCategory {
+    Buff,
+    Debuff,
+    /// 解除の対象にならない
+    Neutral,
+}
+
+/// オーラの効果を受ける対象
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuraTarget {
+    /// 所有者自身を含む味方全員
+    Allies,
+    /// 敵全員
+    Enemies,
+}
+
+impl AuraTarget {
+    pub fn contains(&self, holder: LtId, target: LtId) -> bool {
+        let same_side = matches!(
+            (holder, target),
+            (LtId::Char(_), LtId::Char(_)) | (LtId::Enemy, LtId::Enemy)
+        );
+        match self {
+            AuraTarget::Allies => same_side,
+            AuraTarget::Enemies => !same_side,
+        }
+    }
+}
+
+/// 解除するパッシブの条件
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassiveFilter {
+    Category(PassiveCategory),
+    Tag(PassiveTag),
+}
+
+impl PassiveFilter {
+    pub fn matches(&self, info: &PassiveInfomation) -> bool {
+        match self {
+            PassiveFilter::Category(category) => info.category == *category,
+            PassiveFilter::Tag(tag) => info.tags.contains(tag),
+        }
+    }
 }
 
 //--------------------------------------------------//
@@ -66,12 +143,44 @@ pub struct PassiveInfomation {
 #[allow(unused_variables)]
 pub trait PassiveTrait: Debug + Downcast + DynClone {
     fn info(&self) -> &PassiveInfomation;
-    fn display(&self) -> String;
+    /// 効果量などの要約。名前は含まない
+    fn summary(&self) -> String {
+        String::new()
+    }
     fn should_trash(&self) -> bool;
     fn merge(&mut self, passive: &PassiveBox);
     fn tick(&self, owner: LtId, state: &GameState, effects_buffer: &mut EffectsBuffer);
     fn update(&mut self, msg: &AnyMessageBox);
     fn status(&self, status: &mut PassiveStatus) {}
+    /// 状態によって変わるステータス
+    /// 毎フレーム評価され、変化した場合のみ反映される。そのため反映は1フレーム遅れる
+    fn conditional_status(&self, owner: LtId, state: &GameState, status: &mut PassiveStatus) {}
+    /// Someの場合、所有者が生きている間aura_statusを対象全員に適用する
+    fn aura_target(&self) -> Option<AuraTarget> {
+        None
+    }
+    /// 対象のステータスに加える効果。conditional_statusと同様に反映は1フレーム遅れる
+    fn aura_status(&self, status: &mut PassiveStatus) {}
+    /// 効果時間(フレーム)。Someの場合はPassiveListが残り時間を管理し、0になると取り除かれる
+    /// 付与されたフレームは残り時間が減らないので、付与元の行動順に関係なく同じフレーム数続く
+    fn duration(&self) -> Option<u64> {
+        None
+    }
+    /// Someの場合、付与されてからこのフレーム数ごとにtrigger_intervalが呼ばれる
+    /// 付与後に最初に処理されるフレームが1回目になる
+    fn interval(&self) -> Option<u64> {
+        None
+    }
+    fn trigger_interval(&self, owner: LtId, state: &GameState, effects_buffer: &mut EffectsBuffer) {
+    }
+    /// 同じIDのパッシブが追加された時の挙動
+    fn stack_policy(&self) -> PassiveStackPolicy {
+        PassiveStackPolicy::Merge
+    }
+    /// PassiveStackPolicy::KeepStrongestで比較に使う値
+    fn strength(&self) -> StatusNum {
+        0.0
+    }
     fn trigger_recv_damage(
         &self,
         owner: LtId,
@@ -80,15 +189,105 @@ pub trait PassiveTrait: Debug + Downcast + DynClone {
         effects_buffer: &mut EffectsBuffer,
     ) {
     }
+    /// ownerが与えるダメージを適用前に変更する
+    fn modify_deal_damage(&self, owner: LtId, dmg: &mut PendingDamage, state: &GameState) {}
+    /// ownerが受けるダメージを適用前に変更する
+    fn modify_recv_damage(&self, owner: LtId, dmg: &mut PendingDamage, state: &GameState) {}
+    /// modify_deal_damageとmodify_recv_damageが呼ばれる順番。小さい順
+    /// 同じ場合は攻撃側、被弾側の順で、それぞれパッシブが追加された順
+    fn damage_modify_order(&self) -> i32 {
+        0
+    }
+    /// ownerがダメージを与えた後
+    fn trigger_deal_damage(
+        &self,
+        owner: LtId,
+        dmg: &Damage,
+        state: &GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+    }
+    /// ownerの味方(owner自身は含まない)がダメージを受けた後
+    fn trigger_ally_damaged(
+        &self,
+        owner: LtId,
+        dmg: &Damage,
+        state: &GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+    }
+    /// ownerのHPが回復した後
+    fn trigger_healed(
+        &self,
+        owner: LtId,
+        num: StatusNum,
+        state: &GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+    }
+    /// ownerがスキルを使用した後
+    fn trigger_skill_start(
+        &self,
+        owner: LtId,
+        skill_id: RuntimeSkillId,
+        state: &GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+    }
+    /// ownerのスキルが終了した後
+    fn trigger_skill_end(
+        &self,
+        owner: LtId,
+        skill_id: RuntimeSkillId,
+        state: &GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+    }
+    /// ownerがMPを消費した後
+    fn trigger_consume_mp(
+        &self,
+        owner: LtId,
+        num: StatusNum,
+        state: &GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+    }
+    /// 敵のスキルが発動した時。敵とキャラクターの両方のパッシブで呼ばれる
+    fn trigger_enemy_skill_fired(
+        &self,
+        owner: LtId,
+        skill_id: StaticEnemySkillId,
+        state: &GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+    }
+    /// 戦闘開始時
+    fn trigger_battle_start(
+        &self,
+        owner: LtId,
+        state: &GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+    }
+    /// ownerのHPが0になった時
+    fn trigger_owner_death(
+        &self,
+        owner: LtId,
+        state: &GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+    }
 }
 dyn_clone::clone_trait_object!(PassiveTrait);
 impl_downcast!(PassiveTrait);
 
 #[derive(Debug, Clone)]
 pub struct PassiveList {
-    map: HashMap<StaticPassiveId, PassiveBox>,
+    map: HashMap<StaticPassiveId, PassiveEntry>,
     added_order: AddedOrder,
     cached_status: cached_status::CachedPassiveStatus,
+    /// 前回のtickで評価したPassiveTrait::conditional_statusと、受けているオーラの結果
+    conditional_status: PassiveStatus,
 }
 
 impl PassiveList {
@@ -97,53 +296,255 @@ impl PassiveList {
             map: HashMap::new(),
             added_order: AddedOrder::new(),
             cached_status: cached_status::CachedPassiveStatus::new(),
+            conditional_status: PassiveStatus::default(),
         }
     }
 
-    pub fn display(&self) -> impl Iterator<Item = String> {
-        self.added_order
-            .iter()
-            .map(|id| self.map.get(&id).unwrap().display())
+    /// UI表示用。追加された順
+    pub fn views(&self) -> impl Iterator<Item = PassiveView> {
+        self.added_order_iter().map(|entry| {
+            let passive = entry.first();
+            let info = passive.info();
+            PassiveView {
+                id: info.id,
+                name: info.name,
+                icon_key: info.icon_key,
+                category: info.category,
+                stacks: entry.stacks,
+                remaining_frames: entry.remaining_frames(),
+                max_frames: passive.duration(),
+                summary: passive.summary(),
+                description: info.description,
+            }
+        })
     }
 
-    pub fn add(&mut self, passive: PassiveBox) {
+    /// 付与元のないパッシブとしてframeに追加する
+    pub fn add(&mut self, passive: PassiveBox, frame: u64) {
+        self.add_with_source(passive, None, frame);
+    }
+
+    /// 同じIDのパッシブが既にある場合はPassiveTrait::stack_policyに従う
+    pub(crate) fn add_with_source(
+        &mut self,
+        passive: PassiveBox,
+        source: Option<LtId>,
+        frame: u64,
+    ) {
         assert!(!passive.should_trash());
+        assert!(passive.duration() != Some(0));
+        assert!(!matches!(
+            passive.stack_policy(),
+            PassiveStackPolicy::Stack { max: 0 }
+        ));
+
+        let id = passive.info().id;
 
-        match self.map.entry(passive.info().id) {
+        match self.map.entry(id) {
             hash_map::Entry::Occupied(mut entry) => {
-                entry.get_mut().merge(&passive);
+                entry.get_mut().add(passive, source, frame);
 
-                if entry.get().should_trash() {
-                    self.added_order.remove_expect(passive.info().id);
+                if entry.get().is_empty() {
+                    self.added_order.remove_expect(id);
                     entry.remove();
                 }
             }
             hash_map::Entry::Vacant(entry) => {
-                self.added_order.add(passive.info().id);
-                entry.insert(passive);
+                self.added_order.add(id);
+                entry.insert(PassiveEntry::new(passive, source, frame));
             }
         }
 
         self.cached_status.need_update();
     }
 
+    /// 存在しない場合は何もしない
+    pub(crate) fn remove(&mut self, id: StaticPassiveId) {
+        if self.map.remove(&id).is_some() {
+            self.added_order.remove_expect(id);
+            self.cached_status.need_update();
+        }
+    }
+
+    /// 効果時間が設定されていないパッシブの場合はNone
+    /// 付与元ごとに独立している場合は最も長いもの
+    pub fn remaining_frames(&self, id: StaticPassiveId) -> Option<u64> {
+        self.map.get(&id).and_then(|e| e.remaining_frames())
+    }
+
+    /// 存在しない場合は0
+    pub fn stacks(&self, id: StaticPassiveId) -> u32 {
+        self.map.get(&id).map_or(0, |e| e.stacks)
+    }
+
+    /// 追加された順
+    /// 付与元ごとに独立している場合は最初に追加されたものだけ
+    pub fn iter(&self) -> impl Iterator<Item = &PassiveBox> {
+        self.added_order_iter().map(|e| e.first())
+    }
+
+    /// 効果時間が設定されているパッシブの残り時間を1フレーム減らす
+    /// frameに付与されたものは減らさない
+    /// 残り時間が0になったパッシブは呼び出し元が取り除く必要がある
+    pub(crate) fn decrement_remaining_frames(&mut self, frame: u64) {
+        self.map
+            .values_mut()
+            .for_each(|e| e.decrement_remaining_frames(frame));
+    }
+
+    pub(crate) fn increment_elapsed_frames(&mut self) {
+        self.map
+            .values_mut()
+            .for_each(|e| e.increment_elapsed_frames());
+    }
+
+    /// 残り時間が切れたパッシブを取り除く
+    ///
+    /// ExpirePassiveを発行した後、同じフレームで先に効果時間が延長・更新されている場合があるので
+    /// 適用時の残り時間で判定し直す
+    pub(crate) fn remove_expired(&mut self, id: StaticPassiveId) {
+        let hash_map::Entry::Occupied(mut entry) = self.map.entry(id) else {
+            return;
+        };
+        if !entry.get().is_expiring() {
+            return;
+        }
+
+        entry.get_mut().remove_expired();
+        if entry.get().is_empty() {
+            entry.remove();
+            self.added_order.remove_expect(id);
+        }
+        self.cached_status.need_update();
+    }
+
+    /// 残り時間をパッシブ本来の効果時間に戻す
+    /// 存在しない場合や効果時間が設定されていない場合は何もしない
+    pub(crate) fn refresh_remaining_frames(&mut self, id: StaticPassiveId, frame: u64) {
+        if let Some(entry) = self.map.get_mut(&id) {
+            entry.refresh(frame);
+        }
+    }
+
+    /// 残り時間を延長する
+    /// 存在しない場合や効果時間が設定されていない場合は何もしない
+    pub(crate) fn extend_remaining_frames(&mut self, id: StaticPassiveId, frames: u64) {
+        if let Some(entry) = self.map.get_mut(&id) {
+            entry.extend(frames);
+        }
+    }
+
+    /// 条件に合うパッシブを新しく追加されたものから最大num個取り除く
+    /// Neutralのパッシブはタグが一致しても取り除かない
+    pub(crate) fn dispel(&mut self, filter: PassiveFilter, num: usize) {
+        let ids = self
+            .added_order_iter()
+            .map(|e| e.first().info())
+            .filter(|info| info.category != PassiveCategory::Neutral && filter.matches(info))
+            .map(|info| info.id)
+            .collect::<Vec<_>>();
+
+        ids.into_iter()
+            .rev()
+            .take(num)
+            .for_each(|id| self.remove(id));
+    }
+
+    pub fn contains(&self, id: StaticPassiveId) -> bool {
+        self.map.contains_key(&id)
+    }
+
     pub fn status(&self) -> std::cell::Ref<'_, PassiveStatus> {
-        self.cached_status.get(self.map.values())
+        self.cached_status
+            .get(self.map.values(), &self.conditional_status)
     }
 
-    fn added_order_iter(&self) -> impl Iterator<Item = &PassiveBox> {
+    fn added_order_iter(&self) -> impl Iterator<Item = &PassiveEntry> {
         self.added_order.iter().map(|id| self.map.get(&id).unwrap())
     }
 
+    /// 付与元ごとに独立しているものも含めた全てのパッシブ。追加された順
+    pub(crate) fn instances(&self) -> impl Iterator<Item = &PassiveBox> {
+        self.added_order_iter()
+            .flat_map(|e| e.instances.iter().map(|i| &i.passive))
+    }
+
+    /// スタック数の分だけ繰り返す
+    pub(crate) fn stacked_instances(&self) -> impl Iterator<Item = &PassiveBox> {
+        self.added_order_iter().flat_map(|e| {
+            e.instances
+                .iter()
+                .flat_map(|i| std::iter::repeat_n(&i.passive, e.stacks as usize))
+        })
+    }
+
+    fn eval_conditional_status(&self, owner_id: LtId, state: &GameState) -> PassiveStatus {
+        let mut status = PassiveStatus::default();
+        self.stacked_instances()
+            .for_each(|p| p.conditional_status(owner_id, state, &mut status));
+
+        state
+            .get_lts()
+            .filter(|(_, lt)| !lt.is_dead())
+            .for_each(|(holder_id, lt)| {
+                lt.passive
+                    .stacked_instances()
+                    .filter(|p| {
+                        p.aura_target()
+                            .is_some_and(|t| t.contains(holder_id, owner_id))
+                    })
+                    .for_each(|p| p.aura_status(&mut status));
+            });
+        status
+    }
+
     pub(crate) fn tick(
         &self,
         owner_id: LtId,
         state: &GameState,
         effects_buffer: &mut EffectsBuffer,
     ) {
-        self.added_order_iter().for_each(|p| {
+        self.instances().for_each(|p| {
             p.tick(owner_id, state, effects_buffer);
         });
+
+        self.added_order_iter()
+            .flat_map(|e| e.interval_instances())
+            .for_each(|p| p.trigger_interval(owner_id, state, effects_buffer));
+        if self.map.values().any(|e| e.has_interval()) {
+            effects_buffer.push(Effect::IncrementPassiveElapsedFrames {
+                target_id: owner_id,
+            });
+        }
+
+        let conditional_status = self.eval_conditional_status(owner_id, state);
+        if conditional_status != self.conditional_status {
+            effects_buffer.push(Effect::UpdateConditionalPassiveStatus {
+                target_id: owner_id,
+                status: Box::new(conditional_status),
+            });
+        }
+
+        self.added_order
+            .iter()
+            .filter(|id| self.map.get(id).unwrap().is_expiring())
+            .for_each(|passive_id| {
+                effects_buffer.push(Effect::ExpirePassive {
+                    target_id: owner_id,
+                    passive_id,
+                });
+            });
+
+        if self.map.values().any(|e| e.has_duration()) {
+            effects_buffer.push(Effect::DecrementPassiveRemainingFrames {
+                target_id: owner_id,
+            });
+        }
+    }
+
+    pub(crate) fn set_conditional_status(&mut self, status: PassiveStatus) {
+        self.conditional_status = status;
+        self.cached_status.need_update();
     }
 
     pub(crate) fn update(&mut self, id: StaticPassiveId, msg: &AnyMessageBox) {
@@ -153,7 +554,7 @@ impl PassiveList {
         };
 
         entry.get_mut().update(msg);
-        if entry.get().should_trash() {
+        if entry.get().is_empty() {
             entry.remove();
             self.added_order.remove_expect(id);
         }
@@ -167,7 +568,7 @@ impl PassiveList {
         state: &GameState,
         effects_buffer: &mut EffectsBuffer,
     ) {
-        self.added_order_iter().for_each(|p| {
+        self.instances().for_each(|p| {
             p.trigger_recv_damage(owner, dmg, state, effects_buffer);
         });
     }
@@ -178,3 +579,141 @@ impl Default for PassiveList {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        StatusNum,
+        any_message::AnyMessageBox,
+        core_actor::EffectsBuffer,
+        game_state::GameState,
+        passive::{
+            PassiveBox, PassiveCategory, PassiveInfomation, PassiveList, PassiveTrait,
+            stack_policy::PassiveStackPolicy, status::PassiveStatus,
+        },
+        runtime_id::LtId,
+    };
+
+    #[derive(Debug, Clone)]
+    struct TestPassive {
+        policy: PassiveStackPolicy,
+        add_str: StatusNum,
+    }
+    impl PassiveTrait for TestPassive {
+        fn info(&self) -> &PassiveInfomation {
+            &PassiveInfomation {
+                id: 1,
+                name: "",
+                icon_key: "",
+                description: "",
+                category: PassiveCategory::Neutral,
+                tags: &[],
+            }
+        }
+        fn should_trash(&self) -> bool {
+            false
+        }
+        fn merge(&mut self, passive: &PassiveBox) {
+            self.add_str += passive.downcast_ref::<Self>().unwrap().add_str;
+        }
+        fn tick(&self, _owner: LtId, _state: &GameState, _effects_buffer: &mut EffectsBuffer) {}
+        fn update(&mut self, _msg: &AnyMessageBox) {}
+        fn status(&self, status: &mut PassiveStatus) {
+            status.add_str += self.add_str;
+        }
+        fn duration(&self) -> Option<u64> {
+            Some(10)
+        }
+        fn stack_policy(&self) -> PassiveStackPolicy {
+            self.policy
+        }
+        fn strength(&self) -> StatusNum {
+            self.add_str
+        }
+    }
+
+    fn add_str_after(policy: PassiveStackPolicy, add_strs: &[StatusNum]) -> StatusNum {
+        let mut list = PassiveList::new();
+        for add_str in add_strs {
+            list.add(
+                PassiveBox::new(TestPassive {
+                    policy,
+                    add_str: *add_str,
+                }),
+                0,
+            );
+        }
+        list.status().add_str
+    }
+
+    #[test]
+    fn test_stack_policies() {
+        assert_eq!(add_str_after(PassiveStackPolicy::Merge, &[1.0, 2.0]), 3.0);
+        assert_eq!(add_str_after(PassiveStackPolicy::Replace, &[3.0, 2.0]), 2.0);
+        assert_eq!(
+            add_str_after(PassiveStackPolicy::RefreshDuration, &[3.0, 2.0]),
+            3.0
+        );
+        assert_eq!(
+            add_str_after(PassiveStackPolicy::KeepStrongest, &[3.0, 2.0]),
+            3.0
+        );
+        assert_eq!(
+            add_str_after(PassiveStackPolicy::KeepStrongest, &[2.0, 3.0]),
+            3.0
+        );
+        assert_eq!(
+            add_str_after(PassiveStackPolicy::Stack { max: 2 }, &[1.0, 1.0, 1.0]),
+            2.0
+        );
+    }
+
+    #[test]
+    fn test_stack_refresh_duration() {
+        let mut list = PassiveList::new();
+        let passive = PassiveBox::new(TestPassive {
+            policy: PassiveStackPolicy::Stack { max: 3 },
+            add_str: 1.0,
+        });
+        list.add(passive.clone(), 0);
+        // 付与されたフレームは減らない
+        list.decrement_remaining_frames(0);
+        assert_eq!(list.remaining_frames(1), Some(10));
+        list.decrement_remaining_frames(1);
+        assert_eq!(list.remaining_frames(1), Some(9));
+
+        list.add(passive, 1);
+        assert_eq!(list.remaining_frames(1), Some(10));
+        assert_eq!(list.stacks(1), 2);
+
+        let view = list.views().next().unwrap();
+        assert_eq!(view.stacks, 2);
+        assert_eq!(view.remaining_frames, Some(10));
+        assert_eq!(view.max_frames, Some(10));
+    }
+
+    #[test]
+    fn test_independent_per_source() {
+        let mut list = PassiveList::new();
+        let passive = PassiveBox::new(TestPassive {
+            policy: PassiveStackPolicy::IndependentPerSource,
+            add_str: 1.0,
+        });
+        list.add_with_source(passive.clone(), Some(LtId::Enemy), 0);
+        list.add_with_source(passive.clone(), Some(LtId::Enemy), 0);
+        assert_eq!(list.status().add_str, 1.0);
+
+        list.decrement_remaining_frames(1);
+        list.add_with_source(passive, None, 1);
+        assert_eq!(list.status().add_str, 2.0);
+        assert_eq!(list.remaining_frames(1), Some(10));
+
+        // 付与元ごとに効果時間が切れる
+        for frame in 2..10 {
+            list.decrement_remaining_frames(frame);
+        }
+        list.remove_expired(1);
+        assert_eq!(list.status().add_str, 1.0);
+        assert!(list.contains(1));
+    }
+}
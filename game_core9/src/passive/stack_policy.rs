@@ -0,0 +1,19 @@
+/// 同じIDのパッシブが追加された時の挙動
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PassiveStackPolicy {
+    /// 既存のパッシブのmergeを呼ぶ。効果時間は長い方になる
+    #[default]
+    Merge,
+    /// 新しいパッシブで置き換える
+    Replace,
+    /// 既存のパッシブを残し、効果時間だけ更新する
+    RefreshDuration,
+    /// maxまでスタック数を増やし、効果時間を更新する
+    /// statusやダメージを変更するメソッドはスタック数の回数だけ呼ばれる
+    Stack { max: u32 },
+    /// PassiveTrait::strengthが大きい方を残す。同じ場合は新しい方
+    KeepStrongest,
+    /// 付与元ごとに独立したパッシブとして扱う
+    /// 同じ付与元から追加された場合は置き換える
+    IndependentPerSource,
+}
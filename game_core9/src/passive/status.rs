@@ -8,7 +8,7 @@ use crate::StatusNum;
 // 例外としてPotentialに関するデメリット効果は減算で良い
 // その代わりLtCommon側で0以下の処理などを加える必要はある
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct PassiveStatus {
     /// 魔法攻撃力に乗算させる値 default = 1.0
     pub magic_attuck_mag_buff: BuffMagnificationNum,
@@ -31,6 +31,69 @@ pub struct PassiveStatus {
     /// 被物理ダメージ倍率
     pub recv_physics_dmg_mag: RecvDamageMagnificationNum,
 
+    /// 獲得ヘイトに乗算させる値
+    pub hate_mag_buff: BuffMagnificationNum,
+    /// 獲得ヘイトに乗算させる値
+    pub hate_mag_debuff: DebuffMagnificationNum,
+
+    /// 最大MPに乗算させる値
+    pub max_mp_mag_buff: BuffMagnificationNum,
+    /// 最大MPに乗算させる値
+    pub max_mp_mag_debuff: DebuffMagnificationNum,
+
+    /// 速度に乗算させる値
+    pub speed_mag_buff: BuffMagnificationNum,
+    /// 速度に乗算させる値
+    pub speed_mag_debuff: DebuffMagnificationNum,
+
+    /// 詠唱の進行速度に乗算させる値
+    pub chant_speed_mag_buff: BuffMagnificationNum,
+    /// 詠唱の進行速度に乗算させる値
+    pub chant_speed_mag_debuff: DebuffMagnificationNum,
+
+    /// 詠唱以外の行動の進行速度に乗算させる値
+    pub action_speed_mag_buff: BuffMagnificationNum,
+    /// 詠唱以外の行動の進行速度に乗算させる値
+    pub action_speed_mag_debuff: DebuffMagnificationNum,
+
+    /// MP自然回復量に乗算させる値
+    pub mp_heal_mag_buff: BuffMagnificationNum,
+    /// MP自然回復量に乗算させる値
+    pub mp_heal_mag_debuff: DebuffMagnificationNum,
+
+    /// クールダウンの回復速度に乗算させる値
+    pub cooldown_heal_mag_buff: BuffMagnificationNum,
+    /// クールダウンの回復速度に乗算させる値
+    pub cooldown_heal_mag_debuff: DebuffMagnificationNum,
+
+    /// 消費MPをこの値で割る
+    pub mp_efficiency_buff: BuffMagnificationNum,
+    /// 消費MPをこの値で割る
+    pub mp_efficiency_debuff: DebuffMagnificationNum,
+
+    /// 受けるHP回復量に乗算させる値
+    pub recv_heal_mag_buff: BuffMagnificationNum,
+    /// 受けるHP回復量に乗算させる値
+    pub recv_heal_mag_debuff: DebuffMagnificationNum,
+
+    /// 与えるHP回復量に乗算させる値
+    pub deal_heal_mag_buff: BuffMagnificationNum,
+    /// 与えるHP回復量に乗算させる値
+    pub deal_heal_mag_debuff: DebuffMagnificationNum,
+
+    /// trueの場合、敵の単体攻撃の対象がヘイトに関係なく優先される
+    pub taunt: bool,
+
+    /// trueの場合、スキルの進行とクールダウンの回復が止まる
+    /// 敵の場合はスキルの進行が止まる
+    pub stun: bool,
+    /// trueの場合、魔法スキルを使用できない
+    pub silence: bool,
+    /// 速度と敵のスキルの進行速度に乗算させる値
+    pub slow: DebuffMagnificationNum,
+    /// trueの場合、被弾しても詠唱が中断されたり戻されたりしない
+    pub uninterruptible: bool,
+
     pub add_agi: StatusNum,
     pub add_str: StatusNum,
     pub add_vit: StatusNum,
@@ -54,6 +117,31 @@ impl Default for PassiveStatus {
             magic_attuck_mag_debuff: DebuffMagnificationNum::default(),
             physics_attuck_mag_buff: BuffMagnificationNum::default(),
             physics_attuck_mag_debuff: DebuffMagnificationNum::default(),
+            hate_mag_buff: BuffMagnificationNum::default(),
+            hate_mag_debuff: DebuffMagnificationNum::default(),
+            max_mp_mag_buff: BuffMagnificationNum::default(),
+            max_mp_mag_debuff: DebuffMagnificationNum::default(),
+            speed_mag_buff: BuffMagnificationNum::default(),
+            speed_mag_debuff: DebuffMagnificationNum::default(),
+            chant_speed_mag_buff: BuffMagnificationNum::default(),
+            chant_speed_mag_debuff: DebuffMagnificationNum::default(),
+            action_speed_mag_buff: BuffMagnificationNum::default(),
+            action_speed_mag_debuff: DebuffMagnificationNum::default(),
+            mp_heal_mag_buff: BuffMagnificationNum::default(),
+            mp_heal_mag_debuff: DebuffMagnificationNum::default(),
+            cooldown_heal_mag_buff: BuffMagnificationNum::default(),
+            cooldown_heal_mag_debuff: DebuffMagnificationNum::default(),
+            mp_efficiency_buff: BuffMagnificationNum::default(),
+            mp_efficiency_debuff: DebuffMagnificationNum::default(),
+            recv_heal_mag_buff: BuffMagnificationNum::default(),
+            recv_heal_mag_debuff: DebuffMagnificationNum::default(),
+            deal_heal_mag_buff: BuffMagnificationNum::default(),
+            deal_heal_mag_debuff: DebuffMagnificationNum::default(),
+            taunt: false,
+            stun: false,
+            silence: false,
+            slow: DebuffMagnificationNum::default(),
+            uninterruptible: false,
         }
     }
 }
@@ -62,9 +150,207 @@ impl PassiveStatus {
     pub(crate) fn reset(&mut self) {
         *self = Self::default();
     }
+
+    /// 別に計算したステータスを合成する
+    pub(crate) fn merge(&mut self, other: &PassiveStatus) {
+        // フィールドの追加漏れを防ぐために分解する
+        let PassiveStatus {
+            magic_attuck_mag_buff,
+            magic_attuck_mag_debuff,
+            physics_attuck_mag_buff,
+            physics_attuck_mag_debuff,
+            max_hp_mag_buff,
+            max_hp_mag_debuff,
+            recv_magic_dmg_mag,
+            recv_physics_dmg_mag,
+            hate_mag_buff,
+            hate_mag_debuff,
+            max_mp_mag_buff,
+            max_mp_mag_debuff,
+            speed_mag_buff,
+            speed_mag_debuff,
+            chant_speed_mag_buff,
+            chant_speed_mag_debuff,
+            action_speed_mag_buff,
+            action_speed_mag_debuff,
+            mp_heal_mag_buff,
+            mp_heal_mag_debuff,
+            cooldown_heal_mag_buff,
+            cooldown_heal_mag_debuff,
+            mp_efficiency_buff,
+            mp_efficiency_debuff,
+            recv_heal_mag_buff,
+            recv_heal_mag_debuff,
+            deal_heal_mag_buff,
+            deal_heal_mag_debuff,
+            taunt,
+            stun,
+            silence,
+            slow,
+            uninterruptible,
+            add_agi,
+            add_str,
+            add_vit,
+            add_dex,
+            add_int,
+        } = other;
+
+        self.magic_attuck_mag_buff.merge(magic_attuck_mag_buff);
+        self.magic_attuck_mag_debuff.merge(magic_attuck_mag_debuff);
+        self.physics_attuck_mag_buff.merge(physics_attuck_mag_buff);
+        self.physics_attuck_mag_debuff
+            .merge(physics_attuck_mag_debuff);
+        self.max_hp_mag_buff.merge(max_hp_mag_buff);
+        self.max_hp_mag_debuff.merge(max_hp_mag_debuff);
+        self.recv_magic_dmg_mag.merge(recv_magic_dmg_mag);
+        self.recv_physics_dmg_mag.merge(recv_physics_dmg_mag);
+        self.hate_mag_buff.merge(hate_mag_buff);
+        self.hate_mag_debuff.merge(hate_mag_debuff);
+        self.max_mp_mag_buff.merge(max_mp_mag_buff);
+        self.max_mp_mag_debuff.merge(max_mp_mag_debuff);
+        self.speed_mag_buff.merge(speed_mag_buff);
+        self.speed_mag_debuff.merge(speed_mag_debuff);
+        self.chant_speed_mag_buff.merge(chant_speed_mag_buff);
+        self.chant_speed_mag_debuff.merge(chant_speed_mag_debuff);
+        self.action_speed_mag_buff.merge(action_speed_mag_buff);
+        self.action_speed_mag_debuff.merge(action_speed_mag_debuff);
+        self.mp_heal_mag_buff.merge(mp_heal_mag_buff);
+        self.mp_heal_mag_debuff.merge(mp_heal_mag_debuff);
+        self.cooldown_heal_mag_buff.merge(cooldown_heal_mag_buff);
+        self.cooldown_heal_mag_debuff
+            .merge(cooldown_heal_mag_debuff);
+        self.mp_efficiency_buff.merge(mp_efficiency_buff);
+        self.mp_efficiency_debuff.merge(mp_efficiency_debuff);
+        self.recv_heal_mag_buff.merge(recv_heal_mag_buff);
+        self.recv_heal_mag_debuff.merge(recv_heal_mag_debuff);
+        self.deal_heal_mag_buff.merge(deal_heal_mag_buff);
+        self.deal_heal_mag_debuff.merge(deal_heal_mag_debuff);
+        self.taunt |= taunt;
+        self.stun |= stun;
+        self.silence |= silence;
+        self.slow.merge(slow);
+        self.uninterruptible |= uninterruptible;
+        self.add_agi += add_agi;
+        self.add_str += add_str;
+        self.add_vit += add_vit;
+        self.add_dex += add_dex;
+        self.add_int += add_int;
+    }
+
+    /// デフォルトから変更された項目の要約
+    pub(crate) fn summary(&self) -> String {
+        // フィールドの追加漏れを防ぐために分解する
+        let PassiveStatus {
+            magic_attuck_mag_buff,
+            magic_attuck_mag_debuff,
+            physics_attuck_mag_buff,
+            physics_attuck_mag_debuff,
+            max_hp_mag_buff,
+            max_hp_mag_debuff,
+            recv_magic_dmg_mag,
+            recv_physics_dmg_mag,
+            hate_mag_buff,
+            hate_mag_debuff,
+            max_mp_mag_buff,
+            max_mp_mag_debuff,
+            speed_mag_buff,
+            speed_mag_debuff,
+            chant_speed_mag_buff,
+            chant_speed_mag_debuff,
+            action_speed_mag_buff,
+            action_speed_mag_debuff,
+            mp_heal_mag_buff,
+            mp_heal_mag_debuff,
+            cooldown_heal_mag_buff,
+            cooldown_heal_mag_debuff,
+            mp_efficiency_buff,
+            mp_efficiency_debuff,
+            recv_heal_mag_buff,
+            recv_heal_mag_debuff,
+            deal_heal_mag_buff,
+            deal_heal_mag_debuff,
+            taunt,
+            stun,
+            silence,
+            slow,
+            uninterruptible,
+            add_agi,
+            add_str,
+            add_vit,
+            add_dex,
+            add_int,
+        } = self;
+
+        let mut texts = Vec::new();
+        let mut buff =
+            |name: &str, buff: &BuffMagnificationNum, debuff: &DebuffMagnificationNum| {
+                if buff.get() != 1.0 {
+                    texts.push(format!("{name}+{:.0}%", (buff.get() - 1.0) * 100.0));
+                }
+                if debuff.get() != 1.0 {
+                    texts.push(format!("{name}-{:.0}%", (1.0 - debuff.get()) * 100.0));
+                }
+            };
+        buff("魔法攻撃力", magic_attuck_mag_buff, magic_attuck_mag_debuff);
+        buff(
+            "物理攻撃力",
+            physics_attuck_mag_buff,
+            physics_attuck_mag_debuff,
+        );
+        buff("最大HP", max_hp_mag_buff, max_hp_mag_debuff);
+        buff("獲得ヘイト", hate_mag_buff, hate_mag_debuff);
+        buff("最大MP", max_mp_mag_buff, max_mp_mag_debuff);
+        buff("速度", speed_mag_buff, speed_mag_debuff);
+        buff("詠唱速度", chant_speed_mag_buff, chant_speed_mag_debuff);
+        buff("行動速度", action_speed_mag_buff, action_speed_mag_debuff);
+        buff("MP回復", mp_heal_mag_buff, mp_heal_mag_debuff);
+        buff(
+            "クールダウン回復",
+            cooldown_heal_mag_buff,
+            cooldown_heal_mag_debuff,
+        );
+        buff("MP効率", mp_efficiency_buff, mp_efficiency_debuff);
+        buff("被回復量", recv_heal_mag_buff, recv_heal_mag_debuff);
+        buff("与回復量", deal_heal_mag_buff, deal_heal_mag_debuff);
+
+        for (name, mag) in [
+            ("被魔法ダメージ", recv_magic_dmg_mag),
+            ("被物理ダメージ", recv_physics_dmg_mag),
+        ] {
+            if mag.get() != 1.0 {
+                texts.push(format!("{name}{:.0}%", mag.get() * 100.0));
+            }
+        }
+        if slow.get() != 1.0 {
+            texts.push(format!("スロウ{:.0}%", (1.0 - slow.get()) * 100.0));
+        }
+        for (name, flag) in [
+            ("挑発", taunt),
+            ("スタン", stun),
+            ("沈黙", silence),
+            ("中断無効", uninterruptible),
+        ] {
+            if *flag {
+                texts.push(name.to_string());
+            }
+        }
+        for (name, add) in [
+            ("AGI", add_agi),
+            ("STR", add_str),
+            ("VIT", add_vit),
+            ("DEX", add_dex),
+            ("INT", add_int),
+        ] {
+            if *add != 0.0 {
+                texts.push(format!("{name}{add:+}"));
+            }
+        }
+
+        texts.join(" ")
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct RecvDamageMagnificationNum {
     value: StatusNum,
 }
@@ -81,9 +367,12 @@ impl RecvDamageMagnificationNum {
         assert!(val >= 0.0);
         self.value *= val;
     }
+    fn merge(&mut self, other: &Self) {
+        self.mul(other.value);
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct BuffMagnificationNum {
     value: StatusNum,
 }
@@ -102,9 +391,12 @@ impl BuffMagnificationNum {
         assert!(buff >= 0.0);
         self.value += buff;
     }
+    fn merge(&mut self, other: &Self) {
+        self.add(other.value - 1.0);
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct DebuffMagnificationNum {
     value: StatusNum,
 }
@@ -123,4 +415,7 @@ impl DebuffMagnificationNum {
         assert!(buff <= 1.0);
         self.value *= buff;
     }
+    fn merge(&mut self, other: &Self) {
+        self.mul(other.value);
+    }
 }
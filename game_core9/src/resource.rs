@@ -0,0 +1,111 @@
+//! HP/MP以外のリソース(怒りや集中など)
+
+use crate::StatusNum;
+
+/// リソースの識別子
+pub type ResourceKey = &'static str;
+
+pub const RAGE: ResourceKey = "rage";
+pub const FOCUS: ResourceKey = "focus";
+
+/// リソースが増える条件
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResourceGeneration {
+    /// 与えたダメージ1あたりの獲得量
+    DealDamage(StatusNum),
+    /// 受けたダメージ1あたりの獲得量
+    RecvDamage(StatusNum),
+    /// 1秒あたりの獲得量
+    PerSec(StatusNum),
+}
+
+impl ResourceGeneration {
+    fn num(&self) -> StatusNum {
+        match self {
+            Self::DealDamage(num) | Self::RecvDamage(num) | Self::PerSec(num) => *num,
+        }
+    }
+}
+
+/// キャラクターが持つリソースの定義
+#[derive(Debug, Clone)]
+pub struct ResourceBar {
+    pub key: ResourceKey,
+    pub name: &'static str,
+    pub max: StatusNum,
+    /// 戦闘開始時の値
+    pub initial: StatusNum,
+    pub generation: Vec<ResourceGeneration>,
+}
+
+impl ResourceBar {
+    /// ハンマーの怒り。攻撃と被弾で溜まる
+    pub fn rage() -> Self {
+        Self {
+            key: RAGE,
+            name: "怒り",
+            max: 100.0,
+            initial: 0.0,
+            generation: vec![
+                ResourceGeneration::DealDamage(0.5),
+                ResourceGeneration::RecvDamage(1.0),
+            ],
+        }
+    }
+
+    /// 弓の集中。時間経過で溜まる
+    pub fn focus() -> Self {
+        Self {
+            key: FOCUS,
+            name: "集中",
+            max: 100.0,
+            initial: 0.0,
+            generation: vec![ResourceGeneration::PerSec(5.0)],
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), crate::Error> {
+        if !self.max.is_finite() || self.max <= 0.0 {
+            return Err(crate::Error::InvalidArgument(format!(
+                "Resource max must be positive: {}",
+                self.key
+            )));
+        }
+        if !(0.0..=self.max).contains(&self.initial) {
+            return Err(crate::Error::InvalidArgument(format!(
+                "Resource initial value is out of range: {}",
+                self.key
+            )));
+        }
+        if self
+            .generation
+            .iter()
+            .any(|g| !g.num().is_finite() || g.num() < 0.0)
+        {
+            return Err(crate::Error::InvalidArgument(format!(
+                "Resource generation must not be negative: {}",
+                self.key
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Resource {
+    pub bar: ResourceBar,
+    pub value: StatusNum,
+}
+
+impl Resource {
+    pub fn new(bar: ResourceBar) -> Self {
+        Self {
+            value: bar.initial,
+            bar,
+        }
+    }
+
+    pub fn add(&mut self, num: StatusNum) {
+        self.value = (self.value + num).clamp(0.0, self.bar.max);
+    }
+}
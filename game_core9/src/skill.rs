@@ -8,7 +8,8 @@ use dyn_clone::DynClone;
 
 use crate::{
     StaticSkillId, StatusNum, TimeNum, any_message::AnyMessageBox, core_actor::EffectsBuffer,
-    game_state::GameState, progress_state::ProgressState, runtime_id::RuntimeSkillId,
+    game_state::GameState, progress_state::ProgressState, resource::ResourceKey,
+    runtime_id::RuntimeSkillId,
 };
 
 //--------------------------------------------------//
@@ -44,6 +45,7 @@ impl DerefMut for SkillBox {
 //                       INFO                       //
 //                                                  //
 //--------------------------------------------------//
+#[derive(Debug)]
 pub struct SkillInfomation {
     pub name: &'static str,
     pub description: &'static str,
@@ -67,6 +69,11 @@ pub trait SkillTrait: Debug + Downcast + DynClone {
 
     fn info(&self) -> &SkillInfomation;
 
+    /// trueの場合、沈黙中は使用できない
+    fn is_magic(&self) -> bool {
+        false
+    }
+
     #[allow(unused_variables)]
     fn cost(&self, self_id: RuntimeSkillId, state: &GameState) -> SkillCost {
         let info = self.info();
@@ -78,6 +85,16 @@ pub trait SkillTrait: Debug + Downcast + DynClone {
         .unwrap()
     }
 
+    /// 同時に溜めておける使用回数。回数ごとに個別にクールダウンが回復する
+    fn max_charges(&self) -> u32 {
+        1
+    }
+
+    /// 詠唱中に被弾した時の挙動
+    fn chant_interruption(&self) -> ChantInterruption {
+        ChantInterruption::default()
+    }
+
     #[allow(unused_variables)]
     fn custom_useable(&self, self_id: RuntimeSkillId, state: &GameState) -> SkillCustomUseable {
         SkillCustomUseable::Normal
@@ -91,6 +108,8 @@ pub struct SkillCost {
     need_mp: StatusNum,
     cooldown: TimeNum,
     hate: StatusNum,
+    need_hp: StatusNum,
+    need_resource: Option<(ResourceKey, StatusNum)>,
 }
 
 impl SkillCost {
@@ -117,9 +136,41 @@ impl SkillCost {
             need_mp,
             cooldown,
             hate,
+            need_hp: 0.0,
+            need_resource: None,
         })
     }
 
+    /// 消費HPを設定する。HPが消費HP以下の場合は使用できない
+    pub fn with_need_hp(self, need_hp: StatusNum) -> Result<Self, SkillCostError> {
+        if !need_hp.is_finite() || need_hp < 0.0 {
+            return Err(SkillCostError::InvalidNeedHp(need_hp));
+        }
+        Ok(Self { need_hp, ..self })
+    }
+
+    /// 消費するHP/MP以外のリソースを設定する
+    pub fn with_need_resource(
+        self,
+        key: ResourceKey,
+        num: StatusNum,
+    ) -> Result<Self, SkillCostError> {
+        if !num.is_finite() || num < 0.0 {
+            return Err(SkillCostError::InvalidNeedResource { key, num });
+        }
+        Ok(Self {
+            need_resource: Some((key, num)),
+            ..self
+        })
+    }
+
+    /// 消費MPだけを変更する
+    /// 無限大も許容する
+    pub(crate) fn with_need_mp(&self, need_mp: StatusNum) -> Self {
+        assert!(need_mp >= 0.0);
+        Self { need_mp, ..*self }
+    }
+
     pub fn need_mp(&self) -> StatusNum {
         self.need_mp
     }
@@ -131,6 +182,14 @@ impl SkillCost {
     pub fn hate(&self) -> StatusNum {
         self.hate
     }
+
+    pub fn need_hp(&self) -> StatusNum {
+        self.need_hp
+    }
+
+    pub fn need_resource(&self) -> Option<(ResourceKey, StatusNum)> {
+        self.need_resource
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -140,20 +199,41 @@ pub enum SkillCostError {
         cooldown: TimeNum,
         hate: StatusNum,
     },
+    InvalidNeedHp(StatusNum),
+    InvalidNeedResource {
+        key: ResourceKey,
+        num: StatusNum,
+    },
 }
 
 #[derive(Debug, Clone)]
 pub enum SkillCustomUseable {
     /// need_mp, cooldownなど全ての他の要因を無視してこの値を適用する
     Strong(bool),
-    /// MPが足りない場合でも使用可能。クールダウンは無視しない。
+    /// MPが足りない場合でも使用可能。クールダウンと他のコストは無視しない。
     IgnoreNeedMp,
-    /// クールダウン中でも使用可能。MPは無視しない。
+    /// クールダウン中でも使用可能。MPと他のコストは無視しない。
     IgnoreCooldown,
-    /// 特になし。need_mpとcooldownに依存する
+    /// 特になし。need_mp、need_hp、need_resourceとcooldownに依存する
     Normal,
 }
 
+/// 詠唱中に被弾した時の挙動。デフォルトでは何も起きない
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ChantInterruption {
+    /// 被弾1回ごとに戻される詠唱の進捗(ms)
+    pub pushback_ms: f32,
+    /// 1回の被ダメージが最大HPに対してこの割合以上の場合、詠唱が中断される
+    pub interrupt_threshold: Option<StatusNum>,
+}
+
+/// 詠唱を戻す時にSkillTrait::updateに送られるメッセージ
+/// SkillEffectUnitsを使っている場合はそのまま渡せばいい
+#[derive(Debug, Clone)]
+pub struct ChantPushback {
+    pub ms: f32,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CharSkillProgressKind {
     Chanting,
@@ -5,7 +5,7 @@ use crate::{
     game_state::GameState,
     progress_state::ProgressState,
     runtime_id::RuntimeSkillId,
-    skill::{CharSkillProgress, CharSkillProgressKind},
+    skill::{ChantPushback, CharSkillProgress, CharSkillProgressKind},
     skill_impl_utils::skill_chunks::SkillChunks,
 };
 use std::{fmt::Debug, sync::Arc};
@@ -102,8 +102,11 @@ impl SkillEffectUnits {
         state: &GameState,
         effects_buffer: &mut EffectsBuffer,
     ) -> Result<(), SkillEffectUnitsError> {
-        let (effect_fucntions, msg, ended) =
-            self.tick_inner(state.get_char(id.char_id).lt().speed())?;
+        let lt = state.get_char(id.char_id).lt();
+        let add_progress = self
+            .chunks
+            .frame_progress(self.progress, |kind| lt.skill_progress_speed(kind));
+        let (effect_fucntions, msg, ended) = self.tick_inner(add_progress)?;
 
         for effect_fn in effect_fucntions {
             effect_fn.as_ref()(id, state, effects_buffer);
@@ -163,6 +166,11 @@ impl SkillEffectUnits {
     }
 
     pub fn update<'a>(&mut self, msg: &'a AnyMessageBox) -> Option<&'a AnyMessageBox> {
+        if let Some(pushback) = msg.downcast_ref::<ChantPushback>() {
+            self.pushback(pushback.ms);
+            return None;
+        }
+
         let Some(own_msg) = msg.downcast_ref::<UpdateUnits>() else {
             return Some(msg);
         };
@@ -173,6 +181,19 @@ impl SkillEffectUnits {
         None
     }
 
+    /// 進捗を戻す。発動済みのユニットより前には戻らない
+    fn pushback(&mut self, ms: f32) {
+        assert!(ms >= 0.0);
+        if !self.startd {
+            return;
+        }
+        let floor = match self.step {
+            0 => 0.0,
+            step => self.times[step - 1],
+        };
+        self.progress = (self.progress - ms).max(floor);
+    }
+
     pub fn start(&mut self) -> Result<(), SkillEffectUnitsError> {
         if self.startd {
             return Err(SkillEffectUnitsError::AlreadyStared);
@@ -235,7 +256,7 @@ pub enum SkillEffectUnitsError {
 mod tests {
     use crate::{
         any_message::AnyMessageBox,
-        skill::CharSkillProgressKind,
+        skill::{ChantPushback, CharSkillProgressKind},
         skill_impl_utils::{SkillEffectUnit, SkillEffectUnits, UpdateUnits},
     };
 
@@ -309,4 +330,27 @@ mod tests {
         assert!(last_msg.unwrap().downcast_mut::<UpdateUnits>().is_some());
         assert_eq!(effect_fns_count, 4);
     }
+
+    #[test]
+    fn test_pushback() {
+        let units_vec = vec![
+            SkillEffectUnit::new(10, CharSkillProgressKind::Chanting, |_, _, _| {}).unwrap(),
+            SkillEffectUnit::new(10, CharSkillProgressKind::Chanting, |_, _, _| {}).unwrap(),
+            SkillEffectUnit::new(10, CharSkillProgressKind::Acting, |_, _, _| {}).unwrap(),
+        ];
+        let mut units = SkillEffectUnits::new(units_vec).unwrap();
+        units.start().unwrap();
+
+        let (_, msg, _) = units.tick_inner(15.0).unwrap();
+        units.update(&msg);
+        let pushback = AnyMessageBox::new(ChantPushback { ms: 3.0 });
+        assert!(units.update(&pushback).is_none());
+        assert_eq_f32(units.progress, 12.0);
+
+        // 発動済みのユニットより前には戻らない
+        units.update(&pushback);
+        units.update(&pushback);
+        assert_eq_f32(units.progress, 10.0);
+        assert_eq!(units.step, 1);
+    }
 }
@@ -55,6 +55,43 @@ impl SkillChunks {
             ProgressState::new(last_item.1, last_item.1).unwrap(),
         )
     }
+
+    /// 1フレームで進む進捗
+    /// フレームの途中で種類が変わる場合は、残りの時間を次の種類の速度で進める
+    pub fn frame_progress(
+        &self,
+        current_progress: f32,
+        speed: impl Fn(CharSkillProgressKind) -> f32,
+    ) -> f32 {
+        assert!(current_progress.is_finite());
+
+        let mut progress = current_progress;
+        // 残りのフレーム
+        let mut remaining = 1.0;
+        let mut chunk_end = 0.0;
+
+        for (kind, time) in &self.chunks {
+            chunk_end += time;
+            if progress >= chunk_end {
+                continue;
+            }
+
+            let speed = speed(*kind);
+            if speed <= 0.0 {
+                break;
+            }
+
+            let need_frames = (chunk_end - progress) / speed;
+            if need_frames >= remaining {
+                progress += speed * remaining;
+                break;
+            }
+            progress = chunk_end;
+            remaining -= need_frames;
+        }
+
+        progress - current_progress
+    }
 }
 
 #[cfg(test)]
@@ -115,4 +152,18 @@ mod tests {
         assert_eq!(kind, CharSkillProgressKind::Acting);
         assert_eq_f32(progress.progress(), 1.0);
     }
+
+    #[test]
+    fn frame_progress_uses_speed_per_kind() {
+        let chunks = make_chunks();
+        let speed = |kind| match kind {
+            CharSkillProgressKind::Chanting => 4.0,
+            CharSkillProgressKind::Acting => 1.0,
+        };
+
+        assert_eq_f32(chunks.frame_progress(0.0, speed), 4.0);
+        assert_eq_f32(chunks.frame_progress(25.0, speed), 1.0);
+        // 0.5フレームで詠唱が終わり、残りの0.5フレームは行動の速度で進む
+        assert_eq_f32(chunks.frame_progress(18.0, speed), 2.5);
+    }
 }
@@ -1,6 +1,6 @@
-use crate::StatusNum;
+use crate::{StatusNum, resource::ResourceBar};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WeaponType {
     Sword,
     MagicBook,
@@ -24,6 +24,15 @@ impl WeaponType {
     pub fn is_shield(&self) -> bool {
         matches!(self, Self::SpearAndShield | Self::SwordAndShield)
     }
+
+    /// 武器の種類ごとに持つリソース
+    pub fn resource_bars(&self) -> Vec<ResourceBar> {
+        match self {
+            Self::Hammer => vec![ResourceBar::rage()],
+            Self::Bow => vec![ResourceBar::focus()],
+            _ => vec![],
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
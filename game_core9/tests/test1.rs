@@ -1,13 +1,30 @@
 use game_core9::{
     any_message::AnyMessageBox,
-    buttle_char::ButtleCharArgs,
-    buttle_enemy::{ButtleEnemyArgs, EnemyInfomation},
-    core_actor::{EffectsBuffer, GameCoreActor, UserInput},
-    enemy_skill::EnemySkill,
+    buttle_char::{ButtleCharArgs, CharCondition},
+    buttle_enemy::{
+        ButtleEnemyArgs, EnemyConditionType, EnemyInfomation, EnemyPhase, EnemyStagger,
+    },
+    core_actor::{EffectsBuffer, GameCoreActor, GameCoreOutput, OutputEvent, UserInput},
+    damage::{Damage, DamageType, PendingDamage},
+    effect::{Effect, HateTransferPer},
+    enemy_action_pattern::{EnemyActionCondition, EnemyActionPattern},
+    enemy_skill::{
+        EnemySkill, EnemySkillAction, EnemySkillActionGroup, EnemySkillTarget, ForecastDamage,
+        ForecastTarget,
+    },
     game_state::GameStateArgs,
+    passive::{
+        AuraTarget, PassiveBox, PassiveCategory, PassiveFilter, PassiveInfomation, PassiveTrait,
+        library, stack_policy::PassiveStackPolicy, status::PassiveStatus,
+    },
     potential::Potential,
-    skill::{SkillBox, SkillTrait},
-    weapon::Weapon,
+    resource::{self, ResourceBar, ResourceKey},
+    runtime_id::LtId,
+    skill::{
+        ChantInterruption, CharSkillProgressKind, SkillBox, SkillCost, SkillInfomation, SkillTrait,
+    },
+    skill_impl_utils::{SkillEffectUnit, SkillEffectUnits},
+    weapon::{Weapon, WeaponType},
 };
 
 fn enemy_skill1() -> EnemySkill {
@@ -18,6 +35,7 @@ fn enemy_skill1() -> EnemySkill {
         start_up_frames: 10,
         recovery_frame: 10,
         actions: vec![],
+        fallback: None,
     }
 }
 
@@ -29,6 +47,7 @@ fn enemy_skill2() -> EnemySkill {
         start_up_frames: 10,
         recovery_frame: 10,
         actions: vec![],
+        fallback: None,
     }
 }
 
@@ -40,37 +59,188 @@ fn enemy_skill3() -> EnemySkill {
         start_up_frames: 10,
         recovery_frame: 10,
         actions: vec![],
+        fallback: None,
     }
 }
 
+/// テスト用のスキルの動作
 #[derive(Debug, Clone)]
-struct Skill {}
-impl SkillTrait for Skill {
-    fn info(&self) -> &game_core9::skill::SkillInfomation {
-        &game_core9::skill::SkillInfomation {
-            name: "",
-            description: "",
-            flaver_text: "",
-            id: 1,
-            default_need_mp: 10.0,
-            defalut_hate: 10.0,
-            defalut_cooldown: 10.0,
+enum TestSkillAction {
+    /// 何もせず、終了しない
+    Stay,
+    /// 使用したフレームで終了する
+    End,
+    /// 敵に物理ダメージを与えてすぐに終了する
+    Attack,
+    /// unitsが終わるまで続く
+    Units(SkillEffectUnits),
+    /// 効果を発生させてすぐに終了する
+    Effects(
+        fn(
+            game_core9::runtime_id::RuntimeSkillId,
+            &game_core9::game_state::GameState,
+            &mut EffectsBuffer,
+        ),
+    ),
+}
+
+/// テスト用のスキル
+/// 必要な項目だけ変更して使う。例: TestSkill { max_charges: 2, ..TestSkill::instant() }
+#[derive(Debug, Clone)]
+struct TestSkill {
+    info: &'static SkillInfomation,
+    action: TestSkillAction,
+    need_hp: f32,
+    need_resource: Option<(ResourceKey, f32)>,
+    max_charges: u32,
+    interruption: ChantInterruption,
+    is_magic: bool,
+}
+
+const STAY_SKILL: SkillInfomation = SkillInfomation {
+    name: "",
+    description: "",
+    flaver_text: "",
+    id: 1,
+    default_need_mp: 10.0,
+    defalut_hate: 10.0,
+    defalut_cooldown: 10.0,
+};
+
+const ATTACK_SKILL: SkillInfomation = SkillInfomation {
+    id: 2,
+    ..STAY_SKILL
+};
+
+const INSTANT_SKILL: SkillInfomation = SkillInfomation {
+    id: 3,
+    default_need_mp: 0.0,
+    defalut_hate: 0.0,
+    defalut_cooldown: 1.0,
+    ..STAY_SKILL
+};
+
+const CHANT_SKILL: SkillInfomation = SkillInfomation {
+    id: 4,
+    ..INSTANT_SKILL
+};
+
+impl TestSkill {
+    fn new(info: &'static SkillInfomation, action: TestSkillAction) -> Self {
+        Self {
+            info,
+            action,
+            need_hp: 0.0,
+            need_resource: None,
+            max_charges: 1,
+            interruption: ChantInterruption::default(),
+            is_magic: false,
         }
     }
+
+    fn stay() -> Self {
+        Self::new(&STAY_SKILL, TestSkillAction::Stay)
+    }
+
+    fn attack() -> Self {
+        Self::new(&ATTACK_SKILL, TestSkillAction::Attack)
+    }
+
+    /// コストが0ですぐに終わる
+    fn instant() -> Self {
+        Self::new(&INSTANT_SKILL, TestSkillAction::End)
+    }
+
+    /// 1000ms詠唱してから100ms行動する
+    fn chant() -> Self {
+        let units = SkillEffectUnits::new(vec![
+            SkillEffectUnit::new(1000, CharSkillProgressKind::Chanting, |_, _, _| {}).unwrap(),
+            SkillEffectUnit::new(100, CharSkillProgressKind::Acting, |_, _, _| {}).unwrap(),
+        ])
+        .unwrap();
+        Self::new(&CHANT_SKILL, TestSkillAction::Units(units))
+    }
+}
+
+impl SkillTrait for TestSkill {
+    fn info(&self) -> &SkillInfomation {
+        self.info
+    }
+    fn is_magic(&self) -> bool {
+        self.is_magic
+    }
     fn tick(
         &self,
-        _owner_id: game_core9::runtime_id::RuntimeSkillId,
-        _state: &game_core9::game_state::GameState,
-        _effects_buffer: &mut EffectsBuffer,
+        owner_id: game_core9::runtime_id::RuntimeSkillId,
+        state: &game_core9::game_state::GameState,
+        effects_buffer: &mut EffectsBuffer,
     ) {
+        match &self.action {
+            TestSkillAction::Stay => {}
+            TestSkillAction::End => {
+                effects_buffer.push(Effect::EndSkill { skill_id: owner_id });
+            }
+            TestSkillAction::Attack => {
+                let dmg = Damage::new_physics_damage(
+                    state,
+                    owner_id.owner_id().into(),
+                    state.get_enemy().lt_id(),
+                    1.0,
+                );
+                effects_buffer.push(Effect::Damage(dmg));
+                effects_buffer.push(Effect::EndSkill { skill_id: owner_id });
+            }
+            TestSkillAction::Units(units) => units.tick(owner_id, state, effects_buffer).unwrap(),
+            TestSkillAction::Effects(effects) => {
+                effects(owner_id, state, effects_buffer);
+                effects_buffer.push(Effect::EndSkill { skill_id: owner_id });
+            }
+        }
+    }
+    fn start(&mut self) {
+        if let TestSkillAction::Units(units) = &mut self.action {
+            units.start().unwrap();
+        }
     }
-    fn start(&mut self) {}
     fn end(&mut self) {
-        
+        if let TestSkillAction::Units(units) = &mut self.action {
+            units.end().unwrap();
+        }
+    }
+    fn update(&mut self, msg: &AnyMessageBox) {
+        if let TestSkillAction::Units(units) = &mut self.action {
+            units.update(msg);
+        }
     }
-    fn update(&mut self, _msg: &AnyMessageBox) {}
     fn current_progress(&self) -> Option<game_core9::skill::CharSkillProgress> {
-        None
+        match &self.action {
+            TestSkillAction::Units(units) => units.current_progress(),
+            _ => None,
+        }
+    }
+    fn cost(
+        &self,
+        _self_id: game_core9::runtime_id::RuntimeSkillId,
+        _state: &game_core9::game_state::GameState,
+    ) -> SkillCost {
+        let cost = SkillCost::new(
+            self.info.default_need_mp,
+            self.info.defalut_cooldown,
+            self.info.defalut_hate,
+        )
+        .unwrap()
+        .with_need_hp(self.need_hp)
+        .unwrap();
+        match self.need_resource {
+            Some((key, num)) => cost.with_need_resource(key, num).unwrap(),
+            None => cost,
+        }
+    }
+    fn max_charges(&self) -> u32 {
+        self.max_charges
+    }
+    fn chant_interruption(&self) -> ChantInterruption {
+        self.interruption
     }
 }
 
@@ -79,21 +249,27 @@ fn char1() -> ButtleCharArgs {
         level: 1,
         name: "",
         potential: Potential::new(10.0, 10.0, 10.0, 10.0, 10.0),
-        skills: vec![SkillBox::new(Skill {})],
+        skills: vec![SkillBox::new(TestSkill::stay())],
         static_id: 1,
         weapon: Weapon {
             m_atk: 1.0,
             p_atk: 1.0,
             ty: game_core9::weapon::WeaponType::Cane,
         },
+        resources: vec![],
     }
 }
 fn args() -> GameStateArgs {
     GameStateArgs {
         chars: vec![char1()],
+        seed: 0,
         enemy: ButtleEnemyArgs {
-            action_patterns: vec![vec![1, 2, 3]],
+            action_patterns: vec![EnemyActionPattern::new(vec![1, 2, 3])],
             default_passive: vec![],
+            phases: vec![],
+            stagger: None,
+            hate_decay_per_sec: 0.0,
+            resistances: vec![],
             info: EnemyInfomation {
                 desctiption: "",
                 name: "",
@@ -119,3 +295,2169 @@ fn test2() {
     let res = core.tick(UserInput::None, &mut output_buffer);
     assert!(res.is_ok());
 }
+
+fn enemy_args(skills: Vec<EnemySkill>, action_patterns: Vec<Vec<u32>>) -> GameStateArgs {
+    let mut args = args();
+    args.enemy.skills = skills;
+    args.enemy.action_patterns = action_patterns
+        .into_iter()
+        .map(EnemyActionPattern::new)
+        .collect();
+    args
+}
+
+#[test]
+fn enemy_skill_consumes_mp() {
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    let mut core = GameCoreActor::new(enemy_args(vec![skill], vec![vec![1]])).unwrap();
+    let before = core.state().get_enemy().lt().mp();
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+
+    let after = core.state().get_enemy().lt().mp();
+    let mp_heal = core.state().get_enemy().lt().mp_heal();
+    assert!((before - 10.0 + mp_heal - after).abs() < 0.001);
+}
+
+#[test]
+fn enemy_skill_fallback_when_mp_is_insufficient() {
+    let mut expensive = enemy_skill1();
+    expensive.need_mp = 100000.0;
+    expensive.fallback = Some(2);
+    let core =
+        GameCoreActor::new(enemy_args(vec![expensive, enemy_skill2()], vec![vec![1]])).unwrap();
+
+    assert_eq!(core.state().get_enemy().view_skills().next().unwrap().id, 2);
+}
+
+#[test]
+fn enemy_skill_fallback_not_found() {
+    let mut skill = enemy_skill1();
+    skill.fallback = Some(100);
+    assert!(GameCoreActor::new(enemy_args(vec![skill], vec![vec![1]])).is_err());
+}
+
+#[test]
+fn enemy_skill_fizzle_does_not_affect_next_skill() {
+    // 行動フレームがなく、フレームを進めると即座に終わるスキル
+    let mut starved = enemy_skill1();
+    starved.need_mp = 100000.0;
+    starved.start_up_frames = 0;
+    starved.recovery_frame = 1;
+
+    let mut affordable = enemy_skill2();
+    affordable.need_mp = 0.0;
+    affordable.start_up_frames = 0;
+    affordable.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::Self_,
+            EnemySkillAction::Damage {
+                ty: DamageType::Physics,
+                dmg_mag: 0.1,
+                count: 1,
+            },
+        )],
+    )];
+
+    let mut core =
+        GameCoreActor::new(enemy_args(vec![starved, affordable], vec![vec![1, 2]])).unwrap();
+    let before = core.state().get_enemy().lt().hp();
+
+    let mut output_buffer = Vec::new();
+    // 1フレーム目で不発、2フレーム目で次のスキルが発動する
+    for _ in 0..2 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+
+    assert!(core.state().get_enemy().lt().hp() < before);
+}
+
+#[test]
+fn enemy_action_pattern_conditions_and_weights() {
+    for _ in 0..10 {
+        let mut args = enemy_args(
+            vec![enemy_skill1(), enemy_skill2(), enemy_skill3()],
+            vec![vec![1], vec![2], vec![3]],
+        );
+        // 満HPなので選ばれない
+        args.enemy.action_patterns[0].conditions = vec![EnemyActionCondition::HpBelow(0.5)];
+        args.enemy.action_patterns[0].weight = 1000;
+        // 重み0なので選ばれない
+        args.enemy.action_patterns[1].weight = 0;
+
+        let core = GameCoreActor::new(args).unwrap();
+        assert!(core.state().get_enemy().view_skills().all(|s| s.id == 3));
+    }
+}
+
+#[test]
+fn enemy_action_pattern_requires_unconditional_pattern() {
+    let mut args = enemy_args(vec![enemy_skill1(), enemy_skill2()], vec![vec![1], vec![2]]);
+    args.enemy.action_patterns[0].conditions = vec![EnemyActionCondition::HpBelow(0.5)];
+    args.enemy.action_patterns[1].conditions = vec![EnemyActionCondition::HpAtLeast(0.5)];
+    assert!(matches!(
+        GameCoreActor::new(args),
+        Err(game_core9::Error::InvalidArgument(_))
+    ));
+
+    // 条件なしのパターンの重みが0の場合も同様
+    let mut args = enemy_args(vec![enemy_skill1(), enemy_skill2()], vec![vec![1], vec![2]]);
+    args.enemy.action_patterns[0].conditions = vec![EnemyActionCondition::HpBelow(0.5)];
+    args.enemy.action_patterns[1].weight = 0;
+    assert!(matches!(
+        GameCoreActor::new(args),
+        Err(game_core9::Error::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn enemy_action_pattern_conditions_rechecked_at_start() {
+    let mut args = enemy_args(vec![enemy_skill1(), enemy_skill2()], vec![vec![1], vec![2]]);
+    args.enemy.action_patterns[0].conditions = vec![EnemyActionCondition::ElapsedFramesBelow(5)];
+    // 条件なしのパターンが必須なので、重みの差で条件を満たす間は選ばれないようにする
+    args.enemy.action_patterns[0].weight = u32::MAX - 1;
+
+    let mut core = GameCoreActor::new(args).unwrap();
+    assert!(core.state().get_enemy().view_skills().all(|s| s.id == 1));
+
+    // 最初のスキルが終わる頃には、補充時に組まれたパターンの条件を満たさなくなっている
+    let mut output_buffer = Vec::new();
+    for _ in 0..20 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+
+    assert!(core.state().get_enemy().view_skills().all(|s| s.id == 2));
+}
+
+#[test]
+fn enemy_phase_change() {
+    let mut self_damage = enemy_skill1();
+    self_damage.start_up_frames = 0;
+    self_damage.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::Self_,
+            EnemySkillAction::Damage {
+                ty: DamageType::Physics,
+                dmg_mag: 0.1,
+                count: 1,
+            },
+        )],
+    )];
+
+    let mut args = enemy_args(vec![self_damage, enemy_skill2()], vec![vec![1]]);
+    args.enemy.phases = vec![EnemyPhase {
+        name: "phase1",
+        hp_threshold: 1.0,
+        action_patterns: Some(vec![EnemyActionPattern::new(vec![2])]),
+        add_passives: vec![],
+        remove_passives: vec![],
+        flush_schedule: true,
+    }];
+    let mut core = GameCoreActor::new(args).unwrap();
+    assert_eq!(core.state().get_enemy().current_phase(), 0);
+
+    let mut output_buffer = Vec::new();
+    let mut phase_changed = 0;
+    for _ in 0..3 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+        for output in output_buffer.drain(..) {
+            if let GameCoreOutput::Event(OutputEvent::EnemyPhaseChanged { phase, name }) = output {
+                assert_eq!(phase, 1);
+                assert_eq!(name, "phase1");
+                phase_changed += 1;
+            }
+        }
+    }
+
+    assert_eq!(phase_changed, 1);
+    assert_eq!(core.state().get_enemy().current_phase(), 1);
+    assert!(
+        core.state()
+            .get_enemy()
+            .view_skills()
+            .skip(1)
+            .all(|s| s.id == 2)
+    );
+}
+
+#[test]
+fn enemy_current_condition() {
+    let mut core = GameCoreActor::new(enemy_args(vec![enemy_skill1()], vec![vec![1]])).unwrap();
+    let mut output_buffer = Vec::new();
+
+    let condition = core.state().get_enemy().current_condition();
+    assert_eq!(condition.ty, EnemyConditionType::StartUp);
+    assert_eq!(condition.progress.progress(), 0.0);
+    assert_eq!(condition.skill.id, 1);
+
+    for _ in 0..5 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+    let condition = core.state().get_enemy().current_condition();
+    assert_eq!(condition.ty, EnemyConditionType::StartUp);
+    assert_eq!(condition.progress.progress(), 0.5);
+
+    for _ in 0..10 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+    let condition = core.state().get_enemy().current_condition();
+    assert_eq!(condition.ty, EnemyConditionType::Recovery);
+    assert_eq!(condition.progress.progress(), 0.5);
+}
+
+#[test]
+fn enemy_current_condition_acting_includes_last_action() {
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 2;
+    skill.actions = vec![
+        EnemySkillActionGroup::new(0, vec![]),
+        EnemySkillActionGroup::new(3, vec![]),
+    ];
+    let mut core = GameCoreActor::new(enemy_args(vec![skill], vec![vec![1]])).unwrap();
+
+    tick_frames(&mut core, 2);
+    let condition = core.state().get_enemy().current_condition();
+    assert_eq!(condition.ty, EnemyConditionType::Acting);
+    assert_eq!(condition.progress.progress(), 0.0);
+
+    // 最後の行動が発動するフレーム
+    tick_frames(&mut core, 3);
+    let condition = core.state().get_enemy().current_condition();
+    assert_eq!(condition.ty, EnemyConditionType::Acting);
+    assert_eq!(condition.progress.progress(), 1.0);
+
+    tick_frames(&mut core, 1);
+    let condition = core.state().get_enemy().current_condition();
+    assert_eq!(condition.ty, EnemyConditionType::Recovery);
+}
+
+#[test]
+fn enemy_skill_first_action_must_be_at_frame_zero() {
+    let mut skill = enemy_skill1();
+    skill.actions = vec![EnemySkillActionGroup::new(1, vec![])];
+    assert!(matches!(
+        GameCoreActor::new(enemy_args(vec![skill], vec![vec![1]])),
+        Err(game_core9::Error::InvalidArgument(_))
+    ));
+}
+
+#[test]
+fn enemy_skill_multi_timing() {
+    let hit = || {
+        (
+            EnemySkillTarget::Self_,
+            EnemySkillAction::Damage {
+                ty: DamageType::Physics,
+                dmg_mag: 0.01,
+                count: 1,
+            },
+        )
+    };
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 2;
+    skill.actions = vec![
+        EnemySkillActionGroup::new(0, vec![hit()]),
+        EnemySkillActionGroup::new(3, vec![hit()]),
+        EnemySkillActionGroup::new(6, vec![hit(), hit()]),
+    ];
+    let mut core = GameCoreActor::new(enemy_args(vec![skill], vec![vec![1]])).unwrap();
+
+    let mut output_buffer = Vec::new();
+    let mut hits_per_frame = Vec::new();
+    for _ in 0..10 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+        let hits = output_buffer
+            .drain(..)
+            .filter(|o| matches!(o, GameCoreOutput::Effect(_)))
+            .count();
+        hits_per_frame.push(hits);
+
+        if hits_per_frame.len() == 5 {
+            let condition = core.state().get_enemy().current_condition();
+            assert_eq!(condition.ty, EnemyConditionType::Acting);
+        }
+    }
+
+    assert_eq!(hits_per_frame, vec![0, 0, 1, 0, 0, 1, 0, 0, 2, 0]);
+}
+
+#[test]
+fn enemy_break() {
+    let mut args = enemy_args(vec![enemy_skill1()], vec![vec![1]]);
+    args.chars[0].skills = vec![SkillBox::new(TestSkill::attack())];
+    args.enemy.stagger = Some(EnemyStagger {
+        max: 1.0,
+        broken_frames: 10,
+        broken_recv_dmg_mag: 2.0,
+    });
+    let mut core = GameCoreActor::new(args).unwrap();
+    let skill_id = core.state().get_chars()[0].get_skills()[0].runtime_id();
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+        .unwrap();
+    for _ in 0..2 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+
+    let broken_events = output_buffer
+        .iter()
+        .filter(|o| matches!(o, GameCoreOutput::Event(OutputEvent::EnemyBroken)))
+        .count();
+    assert_eq!(broken_events, 1);
+
+    let enemy = core.state().get_enemy();
+    assert!(enemy.is_broken());
+    assert_eq!(enemy.broken_recv_dmg_mag(), 2.0);
+    assert_eq!(enemy.stagger_gauge().unwrap().current(), 0.0);
+    assert_eq!(enemy.current_condition().ty, EnemyConditionType::Broken);
+
+    for _ in 0..10 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+    assert!(!core.state().get_enemy().is_broken());
+}
+
+#[test]
+fn enemy_forecast() {
+    let mut skill = enemy_skill1();
+    skill.actions = vec![
+        EnemySkillActionGroup::new(
+            0,
+            vec![(
+                EnemySkillTarget::Single,
+                EnemySkillAction::Damage {
+                    ty: DamageType::Physics,
+                    dmg_mag: 1.0,
+                    count: 2,
+                },
+            )],
+        ),
+        EnemySkillActionGroup::new(
+            10,
+            vec![(
+                EnemySkillTarget::AllChar,
+                EnemySkillAction::Damage {
+                    ty: DamageType::Physics,
+                    dmg_mag: 1.0,
+                    count: 1,
+                },
+            )],
+        ),
+    ];
+    let core = GameCoreActor::new(enemy_args(vec![skill], vec![vec![1]])).unwrap();
+    let state = core.state();
+    let char_id = state.get_chars()[0].lt_id();
+
+    let forecast = state.get_enemy().forecast(state).collect::<Vec<_>>();
+    assert_eq!(forecast.len(), 5);
+
+    let damages = &forecast[0].damages;
+    assert_eq!(damages.len(), 1);
+    assert_eq!(damages[0].target, ForecastTarget::Lt(char_id));
+    assert_eq!(damages[0].ty, DamageType::Physics);
+
+    let expect = Damage::new_physics_damage(state, LtId::Enemy, char_id, 1.0).dmg() * 3.0;
+    assert!((damages[0].dmg - expect).abs() < 0.001);
+}
+
+#[test]
+fn enemy_forecast_fixed_damage() {
+    let mut skill = enemy_skill1();
+    skill.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::Single,
+            EnemySkillAction::Damage {
+                ty: DamageType::Fixed,
+                dmg_mag: 30.0,
+                count: 2,
+            },
+        )],
+    )];
+    let core = GameCoreActor::new(enemy_args(vec![skill], vec![vec![1]])).unwrap();
+    let state = core.state();
+
+    let forecast = state.get_enemy().forecast(state).collect::<Vec<_>>();
+    let damages = &forecast[0].damages;
+    assert_eq!(damages.len(), 1);
+    assert_eq!(damages[0].ty, DamageType::Fixed);
+    assert!((damages[0].dmg - 60.0).abs() < 0.001);
+}
+
+fn target_ids(core: &GameCoreActor, target: EnemySkillTarget) -> Vec<LtId> {
+    let mut skill = enemy_skill1();
+    skill.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            target,
+            EnemySkillAction::Damage {
+                ty: DamageType::Physics,
+                dmg_mag: 1.0,
+                count: 1,
+            },
+        )],
+    )];
+    skill
+        .forecast_damages(core.state())
+        .into_iter()
+        .map(|d| match d.target {
+            ForecastTarget::Lt(id) => id,
+            ForecastTarget::Random(_) => panic!("unexpected random target"),
+        })
+        .collect()
+}
+
+#[test]
+fn enemy_skill_targets() {
+    let mut args = args();
+    let mut weak = char1();
+    weak.potential = Potential::new(10.0, 10.0, 10.0, 15.0, 5.0);
+    args.chars.push(weak);
+    let core = GameCoreActor::new(args).unwrap();
+    let chars = core.state().get_chars();
+    let (first, second) = (chars[0].lt_id(), chars[1].lt_id());
+
+    assert_eq!(target_ids(&core, EnemySkillTarget::LowestHp), vec![second]);
+    assert_eq!(
+        target_ids(&core, EnemySkillTarget::LowestHpPer),
+        vec![first]
+    );
+    assert_eq!(target_ids(&core, EnemySkillTarget::LowestHate), vec![first]);
+    assert_eq!(
+        target_ids(&core, EnemySkillTarget::HighestAttuck),
+        vec![second]
+    );
+    assert!(target_ids(&core, EnemySkillTarget::Chanting).is_empty());
+    assert!(target_ids(&core, EnemySkillTarget::WithPassive(1)).is_empty());
+    assert_eq!(
+        target_ids(&core, EnemySkillTarget::WithoutPassive(1)),
+        vec![first, second]
+    );
+}
+
+fn random_forecast(core: &GameCoreActor, n: u8) -> Vec<ForecastDamage> {
+    let mut skill = enemy_skill1();
+    skill.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::Random(n),
+            EnemySkillAction::Damage {
+                ty: DamageType::Physics,
+                dmg_mag: 1.0,
+                count: 1,
+            },
+        )],
+    )];
+    skill.forecast_damages(core.state())
+}
+
+#[test]
+fn enemy_forecast_random_target() {
+    let mut args = args();
+    let mut weak = char1();
+    weak.potential = Potential::new(10.0, 10.0, 10.0, 15.0, 5.0);
+    args.chars.push(weak);
+    let core = GameCoreActor::new(args).unwrap();
+    let state = core.state();
+
+    // 対象は決めずに人数と1人あたりの最大ダメージを見積もる
+    let damages = random_forecast(&core, 1);
+    assert_eq!(damages.len(), 1);
+    assert_eq!(damages[0].target, ForecastTarget::Random(1));
+    let expect = state
+        .get_chars()
+        .iter()
+        .map(|c| Damage::new_physics_damage(state, LtId::Enemy, c.lt_id(), 1.0).dmg())
+        .fold(0.0, f32::max);
+    assert!((damages[0].dmg - expect).abs() < 0.001);
+
+    assert_eq!(
+        random_forecast(&core, 4)[0].target,
+        ForecastTarget::Random(2)
+    );
+}
+
+#[test]
+fn enemy_forecast_applies_damage_modifiers() {
+    let halve = TestPassive {
+        modify_recv_damage: |dmg| dmg.mul(0.5),
+        ..TestPassive::new(&MODIFY_DAMAGE)
+    };
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    skill.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::AllChar,
+            EnemySkillAction::AddPassive(PassiveBox::new(halve)),
+        )],
+    )];
+    let mut attack = enemy_skill1();
+    attack.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::AllChar,
+            EnemySkillAction::Damage {
+                ty: DamageType::Fixed,
+                dmg_mag: 30.0,
+                count: 1,
+            },
+        )],
+    )];
+    let mut core = GameCoreActor::new(enemy_args(vec![skill], vec![vec![1]])).unwrap();
+    tick_frames(&mut core, 1);
+
+    let damages = attack.forecast_damages(core.state());
+    assert_eq!(damages.len(), 1);
+    assert!((damages[0].dmg - 15.0).abs() < 0.001);
+}
+
+/// テスト用のパッシブ
+/// 必要な項目だけ変更して使う。例: TestPassive { duration: Some(5), ..TestPassive::new(&TIMED) }
+#[derive(Debug, Clone)]
+struct TestPassive {
+    info: &'static PassiveInfomation,
+    duration: Option<u64>,
+    stack_policy: PassiveStackPolicy,
+    /// statusでadd_strに加える値。mergeでは合算され、strengthにも使う
+    add_str: f32,
+    status: fn(&mut PassiveStatus),
+    tick: fn(LtId, &game_core9::game_state::GameState, &mut EffectsBuffer),
+    modify_recv_damage: fn(&mut PendingDamage),
+    damage_modify_order: i32,
+    trigger_battle_start: fn(LtId, &mut EffectsBuffer),
+    trigger_skill_start: fn(LtId, &mut EffectsBuffer),
+}
+
+const fn test_passive_info(
+    id: u32,
+    category: PassiveCategory,
+    tags: &'static [&'static str],
+) -> PassiveInfomation {
+    PassiveInfomation {
+        id,
+        name: "",
+        icon_key: "",
+        description: "",
+        category,
+        tags,
+    }
+}
+
+const TAUNT: PassiveInfomation = test_passive_info(1, PassiveCategory::Buff, &["taunt"]);
+const TIMED: PassiveInfomation = test_passive_info(2, PassiveCategory::Debuff, &["timed"]);
+const HOOK: PassiveInfomation = test_passive_info(3, PassiveCategory::Neutral, &[]);
+const MODIFY_DAMAGE: PassiveInfomation = test_passive_info(4, PassiveCategory::Neutral, &[]);
+const MODIFY_DAMAGE2: PassiveInfomation = test_passive_info(5, PassiveCategory::Neutral, &[]);
+const STATUS: PassiveInfomation = test_passive_info(6, PassiveCategory::Neutral, &[]);
+const POLICY: PassiveInfomation = test_passive_info(20, PassiveCategory::Buff, &[]);
+const EXTEND: PassiveInfomation = test_passive_info(21, PassiveCategory::Neutral, &[]);
+
+impl TestPassive {
+    /// 何もしないパッシブ
+    fn new(info: &'static PassiveInfomation) -> Self {
+        Self {
+            info,
+            duration: None,
+            stack_policy: PassiveStackPolicy::Merge,
+            add_str: 0.0,
+            status: |_| {},
+            tick: |_, _, _| {},
+            modify_recv_damage: |_| {},
+            damage_modify_order: 0,
+            trigger_battle_start: |_, _| {},
+            trigger_skill_start: |_, _| {},
+        }
+    }
+
+    /// 挑発する
+    fn taunt() -> Self {
+        Self {
+            status: |s| s.taunt = true,
+            ..Self::new(&TAUNT)
+        }
+    }
+
+    /// 効果時間だけが設定されている
+    fn timed(duration: u64) -> Self {
+        Self {
+            duration: Some(duration),
+            ..Self::new(&TIMED)
+        }
+    }
+
+    /// 戦闘開始時にTIMEDを得て、スキル使用時にMPを5回復する
+    fn hook() -> Self {
+        Self {
+            trigger_battle_start: |owner, effects_buffer| {
+                effects_buffer.push(Effect::AddPassive {
+                    target_id: owner,
+                    passive: PassiveBox::new(TestPassive::timed(100)),
+                    source: Some(owner),
+                });
+            },
+            trigger_skill_start: |owner, effects_buffer| {
+                effects_buffer.push(Effect::HealMp {
+                    target_id: owner,
+                    num: 5.0,
+                });
+            },
+            ..Self::new(&HOOK)
+        }
+    }
+
+    /// statusだけを変更する
+    fn status(status: fn(&mut PassiveStatus)) -> Self {
+        Self {
+            status,
+            ..Self::new(&STATUS)
+        }
+    }
+
+    /// 同じIDのパッシブが追加された時の挙動を確かめる
+    fn policy(stack_policy: PassiveStackPolicy, duration: u64, add_str: f32) -> Self {
+        Self {
+            duration: Some(duration),
+            stack_policy,
+            add_str,
+            ..Self::new(&POLICY)
+        }
+    }
+}
+
+impl PassiveTrait for TestPassive {
+    fn info(&self) -> &PassiveInfomation {
+        self.info
+    }
+    fn should_trash(&self) -> bool {
+        false
+    }
+    fn merge(&mut self, passive: &PassiveBox) {
+        self.add_str += passive.downcast_ref::<Self>().unwrap().add_str;
+    }
+    fn tick(
+        &self,
+        owner: LtId,
+        state: &game_core9::game_state::GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+        (self.tick)(owner, state, effects_buffer);
+    }
+    fn update(&mut self, _msg: &AnyMessageBox) {}
+    fn status(&self, status: &mut PassiveStatus) {
+        status.add_str += self.add_str;
+        (self.status)(status);
+    }
+    fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+    fn stack_policy(&self) -> PassiveStackPolicy {
+        self.stack_policy
+    }
+    fn strength(&self) -> f32 {
+        self.add_str
+    }
+    fn modify_recv_damage(
+        &self,
+        _owner: LtId,
+        dmg: &mut PendingDamage,
+        _state: &game_core9::game_state::GameState,
+    ) {
+        (self.modify_recv_damage)(dmg);
+    }
+    fn damage_modify_order(&self) -> i32 {
+        self.damage_modify_order
+    }
+    fn trigger_battle_start(
+        &self,
+        owner: LtId,
+        _state: &game_core9::game_state::GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+        (self.trigger_battle_start)(owner, effects_buffer);
+    }
+    fn trigger_skill_start(
+        &self,
+        owner: LtId,
+        _skill_id: game_core9::runtime_id::RuntimeSkillId,
+        _state: &game_core9::game_state::GameState,
+        effects_buffer: &mut EffectsBuffer,
+    ) {
+        (self.trigger_skill_start)(owner, effects_buffer);
+    }
+}
+
+#[test]
+fn hate_decay() {
+    let mut args = enemy_args(vec![enemy_skill1()], vec![vec![1]]);
+    args.chars[0].skills = vec![SkillBox::new(TestSkill::attack())];
+    args.enemy.hate_decay_per_sec = 0.5;
+    let mut core = GameCoreActor::new(args).unwrap();
+    let skill_id = core.state().get_chars()[0].get_skills()[0].runtime_id();
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+        .unwrap();
+    assert_eq!(core.state().get_chars()[0].hate(), 10.0);
+
+    for _ in 0..100 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+    assert!((core.state().get_chars()[0].hate() - 5.0).abs() < 0.01);
+}
+
+#[test]
+fn hate_manipulation_effects() {
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    skill.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::AllChar,
+            EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::status(|s| {
+                s.hate_mag_buff.add(1.0)
+            }))),
+        )],
+    )];
+    let mut args = enemy_args(vec![skill], vec![vec![1]]);
+    args.chars = vec![char1(), char1()];
+    args.chars[0].skills = vec![
+        SkillBox::new(TestSkill::attack()),
+        SkillBox::new(TestSkill::new(
+            &INSTANT_SKILL,
+            TestSkillAction::Effects(|id, state, effects_buffer| {
+                effects_buffer.push(Effect::TransferHate {
+                    from: id.owner_id(),
+                    to: state.get_chars()[1].runtime_id(),
+                    per: HateTransferPer::new(0.5).unwrap(),
+                });
+            }),
+        )),
+        SkillBox::new(TestSkill::new(
+            &CHANT_SKILL,
+            TestSkillAction::Effects(|id, state, effects_buffer| {
+                effects_buffer.push(Effect::MulHate {
+                    target_id: id.owner_id(),
+                    mag: 0.5,
+                });
+                effects_buffer.push(Effect::ReduceHate {
+                    target_id: state.get_chars()[1].runtime_id(),
+                    num: 100.0,
+                });
+            }),
+        )),
+    ];
+    let mut core = GameCoreActor::new(args).unwrap();
+    let skill_ids = core.state().get_chars()[0]
+        .get_skills()
+        .iter()
+        .map(|s| s.runtime_id())
+        .collect::<Vec<_>>();
+    let hates = |core: &GameCoreActor| {
+        let chars = core.state().get_chars();
+        (chars[0].hate(), chars[1].hate())
+    };
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    core.tick(
+        UserInput::UseSkill {
+            skill_id: skill_ids[0],
+        },
+        &mut output_buffer,
+    )
+    .unwrap();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    // 獲得ヘイト2倍
+    assert_eq!(hates(&core), (20.0, 0.0));
+
+    // 移す分には獲得ヘイトの倍率をかけない
+    core.tick(
+        UserInput::UseSkill {
+            skill_id: skill_ids[1],
+        },
+        &mut output_buffer,
+    )
+    .unwrap();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    assert_eq!(hates(&core), (10.0, 10.0));
+
+    // ヘイトは0未満にならない
+    core.tick(
+        UserInput::UseSkill {
+            skill_id: skill_ids[2],
+        },
+        &mut output_buffer,
+    )
+    .unwrap();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    assert_eq!(hates(&core), (5.0, 0.0));
+}
+
+#[test]
+fn hate_transfer_per_out_of_range_is_error() {
+    assert!(HateTransferPer::new(1.5).is_err());
+    assert!(HateTransferPer::new(-0.1).is_err());
+    assert!(HateTransferPer::new(1.0).is_ok());
+}
+
+#[test]
+fn taunt_overrides_hate() {
+    let mut taunt = enemy_skill1();
+    taunt.start_up_frames = 0;
+    taunt.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::LowestHp,
+            EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::taunt())),
+        )],
+    )];
+    let mut args = enemy_args(vec![taunt], vec![vec![1]]);
+    args.chars[0].skills = vec![SkillBox::new(TestSkill::attack())];
+    let mut weak = char1();
+    weak.potential = Potential::new(10.0, 10.0, 10.0, 15.0, 5.0);
+    args.chars.push(weak);
+    let mut core = GameCoreActor::new(args).unwrap();
+    let chars = core.state().get_chars();
+    let (first, second) = (chars[0].lt_id(), chars[1].lt_id());
+    let skill_id = chars[0].get_skills()[0].runtime_id();
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+        .unwrap();
+    assert!(core.state().get_chars()[0].hate() > core.state().get_chars()[1].hate());
+    assert!(core.state().get_chars()[1].lt().is_taunting());
+
+    assert_eq!(target_ids(&core, EnemySkillTarget::Single), vec![second]);
+    assert_eq!(
+        target_ids(&core, EnemySkillTarget::Multi(2)),
+        vec![second, first]
+    );
+}
+
+#[test]
+fn timed_passive_expires() {
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    skill.recovery_frame = 100;
+    skill.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::Self_,
+            EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::timed(5))),
+        )],
+    )];
+    let mut core = GameCoreActor::new(enemy_args(vec![skill], vec![vec![1]])).unwrap();
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    let passive = &core.state().get_enemy().lt().passive;
+    assert_eq!(passive.remaining_frames(TIMED.id), Some(5));
+
+    for _ in 0..4 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+    let passive = &core.state().get_enemy().lt().passive;
+    assert_eq!(passive.remaining_frames(TIMED.id), Some(1));
+    assert!(
+        !output_buffer
+            .iter()
+            .any(|o| matches!(o, GameCoreOutput::Event(OutputEvent::PassiveExpired { .. })))
+    );
+
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    assert!(!core.state().get_enemy().lt().passive.contains(TIMED.id));
+    assert!(output_buffer.iter().any(|o| matches!(
+        o,
+        GameCoreOutput::Event(OutputEvent::PassiveExpired {
+            target_id: LtId::Enemy,
+            passive_id
+        }) if *passive_id == TIMED.id
+    )));
+}
+
+#[test]
+fn passive_from_earlier_actor_keeps_full_duration() {
+    // キャラクターは敵より先に処理されるので、付与が敵の残り時間の減少より先に行われる
+    let mut args = enemy_args(vec![enemy_skill1()], vec![vec![1]]);
+    // 残り時間の減少が毎フレーム発行されるように効果時間のあるパッシブを持たせる
+    args.enemy.default_passive = vec![PassiveBox::new(TestPassive::policy(
+        PassiveStackPolicy::Replace,
+        100,
+        0.0,
+    ))];
+    args.chars[0].skills = vec![SkillBox::new(TestSkill::new(
+        &INSTANT_SKILL,
+        TestSkillAction::Effects(|id, _state, effects_buffer| {
+            effects_buffer.push(Effect::AddPassive {
+                target_id: LtId::Enemy,
+                passive: PassiveBox::new(TestPassive::timed(5)),
+                source: Some(id.owner_id().into()),
+            });
+        }),
+    ))];
+    let mut core = GameCoreActor::new(args).unwrap();
+    let skill_id = core.state().get_chars()[0].get_skills()[0].runtime_id();
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+        .unwrap();
+    while !core.state().get_enemy().lt().passive.contains(TIMED.id) {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+    let passive = &core.state().get_enemy().lt().passive;
+    assert_eq!(passive.remaining_frames(TIMED.id), Some(5));
+}
+
+/// 敵が指定した行動フレームで自身へパッシブを付与する
+/// 行動フレームnの付与はn + 1回目のtickで行われる
+fn policy_core(default_passive: Vec<TestPassive>, adds: Vec<(u64, TestPassive)>) -> GameCoreActor {
+    // 最初の行動は相対フレーム0にする必要があるので、最初の付与までを準備フレームにする
+    let start_up_frames = adds.first().map_or(0, |(frame, _)| *frame);
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = start_up_frames;
+    skill.recovery_frame = 100;
+    skill.actions = adds
+        .into_iter()
+        .map(|(frame, passive)| {
+            EnemySkillActionGroup::new(
+                frame - start_up_frames,
+                vec![(
+                    EnemySkillTarget::Self_,
+                    EnemySkillAction::AddPassive(PassiveBox::new(passive)),
+                )],
+            )
+        })
+        .collect();
+    let mut args = enemy_args(vec![skill], vec![vec![1]]);
+    args.enemy.default_passive = default_passive.into_iter().map(PassiveBox::new).collect();
+    GameCoreActor::new(args).unwrap()
+}
+
+fn tick_frames(core: &mut GameCoreActor, frames: usize) {
+    let mut output_buffer = Vec::new();
+    for _ in 0..frames {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+}
+
+fn policy_add_str(core: &GameCoreActor) -> f32 {
+    core.state().get_enemy().lt().passive.status().add_str
+}
+
+#[test]
+fn stack_policy_merge() {
+    let policy = PassiveStackPolicy::Merge;
+    let mut core = policy_core(
+        vec![],
+        vec![
+            (0, TestPassive::policy(policy, 10, 1.0)),
+            (1, TestPassive::policy(policy, 30, 2.0)),
+        ],
+    );
+    tick_frames(&mut core, 2);
+
+    let passive = &core.state().get_enemy().lt().passive;
+    assert!((policy_add_str(&core) - 3.0).abs() < 0.001);
+    assert_eq!(passive.stacks(POLICY.id), 1);
+    // 効果時間は長い方になる
+    assert_eq!(passive.remaining_frames(POLICY.id), Some(30));
+}
+
+#[test]
+fn stack_policy_replace() {
+    let policy = PassiveStackPolicy::Replace;
+    let mut core = policy_core(
+        vec![],
+        vec![
+            (0, TestPassive::policy(policy, 30, 3.0)),
+            (1, TestPassive::policy(policy, 10, 1.0)),
+        ],
+    );
+    tick_frames(&mut core, 2);
+
+    let passive = &core.state().get_enemy().lt().passive;
+    assert!((policy_add_str(&core) - 1.0).abs() < 0.001);
+    assert_eq!(passive.remaining_frames(POLICY.id), Some(10));
+}
+
+#[test]
+fn stack_policy_refresh_duration() {
+    let policy = PassiveStackPolicy::RefreshDuration;
+    let mut core = policy_core(
+        vec![TestPassive::policy(policy, 10, 1.0)],
+        vec![(4, TestPassive::policy(policy, 10, 3.0))],
+    );
+    tick_frames(&mut core, 4);
+    // 付与されたフレームは残り時間が減らない
+    assert_eq!(
+        core.state()
+            .get_enemy()
+            .lt()
+            .passive
+            .remaining_frames(POLICY.id),
+        Some(7)
+    );
+
+    // 既存のパッシブが残り、効果時間だけが戻る
+    tick_frames(&mut core, 1);
+    let passive = &core.state().get_enemy().lt().passive;
+    assert!((policy_add_str(&core) - 1.0).abs() < 0.001);
+    assert_eq!(passive.remaining_frames(POLICY.id), Some(10));
+}
+
+#[test]
+fn stack_policy_stack() {
+    let policy = PassiveStackPolicy::Stack { max: 3 };
+    let mut core = policy_core(
+        vec![],
+        (0..4)
+            .map(|frame| (frame, TestPassive::policy(policy, 10, 1.0)))
+            .collect(),
+    );
+    tick_frames(&mut core, 4);
+
+    let passive = &core.state().get_enemy().lt().passive;
+    assert_eq!(passive.stacks(POLICY.id), 3);
+    assert!((policy_add_str(&core) - 3.0).abs() < 0.001);
+}
+
+#[test]
+fn stack_policy_keep_strongest() {
+    let policy = PassiveStackPolicy::KeepStrongest;
+    let mut core = policy_core(
+        vec![],
+        vec![
+            (0, TestPassive::policy(policy, 10, 3.0)),
+            (1, TestPassive::policy(policy, 10, 1.0)),
+            (2, TestPassive::policy(policy, 10, 5.0)),
+        ],
+    );
+    tick_frames(&mut core, 2);
+    assert!((policy_add_str(&core) - 3.0).abs() < 0.001);
+
+    tick_frames(&mut core, 1);
+    assert!((policy_add_str(&core) - 5.0).abs() < 0.001);
+}
+
+#[test]
+fn stack_policy_independent_per_source() {
+    let policy = PassiveStackPolicy::IndependentPerSource;
+    // 初期パッシブは付与元なし、敵スキルで付与したものは付与元が敵になる
+    let mut core = policy_core(
+        vec![TestPassive::policy(policy, 100, 1.0)],
+        vec![
+            (0, TestPassive::policy(policy, 10, 2.0)),
+            (1, TestPassive::policy(policy, 10, 2.0)),
+        ],
+    );
+    tick_frames(&mut core, 2);
+    assert!((policy_add_str(&core) - 3.0).abs() < 0.001);
+
+    // 付与元ごとに効果時間が切れる
+    tick_frames(&mut core, 10);
+    let passive = &core.state().get_enemy().lt().passive;
+    assert!((policy_add_str(&core) - 1.0).abs() < 0.001);
+    assert!(passive.contains(POLICY.id));
+}
+
+#[test]
+fn passive_extended_in_same_frame_does_not_expire() {
+    let mut args = enemy_args(vec![enemy_skill1()], vec![vec![1]]);
+    args.enemy.default_passive = vec![
+        // POLICYが切れる直前に効果時間を延長する
+        PassiveBox::new(TestPassive {
+            tick: |owner, state, effects_buffer| {
+                let remaining = state.get_lt(owner).passive.remaining_frames(POLICY.id);
+                if remaining == Some(1) {
+                    effects_buffer.push(Effect::ExtendPassiveDuration {
+                        target_id: owner,
+                        passive_id: POLICY.id,
+                        frames: 10,
+                    });
+                }
+            },
+            ..TestPassive::new(&EXTEND)
+        }),
+        PassiveBox::new(TestPassive::policy(PassiveStackPolicy::Replace, 5, 1.0)),
+    ];
+    let mut core = GameCoreActor::new(args).unwrap();
+
+    let mut output_buffer = Vec::new();
+    // 付与されたフレームは残り時間が減らないので、6フレーム目に延長される
+    for _ in 0..6 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+
+    let passive = &core.state().get_enemy().lt().passive;
+    assert_eq!(passive.remaining_frames(POLICY.id), Some(10));
+    assert!(
+        !output_buffer
+            .iter()
+            .any(|o| matches!(o, GameCoreOutput::Event(OutputEvent::PassiveExpired { .. })))
+    );
+}
+
+#[test]
+fn passive_trigger_hooks() {
+    let mp_after_skill = |with_passive: bool| {
+        let mut skill = enemy_skill1();
+        skill.start_up_frames = 0;
+        skill.need_mp = 0.0;
+        if with_passive {
+            skill.actions = vec![EnemySkillActionGroup::new(
+                0,
+                vec![(
+                    EnemySkillTarget::AllChar,
+                    EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::hook())),
+                )],
+            )];
+        }
+        let mut args = enemy_args(vec![skill], vec![vec![1]]);
+        args.chars[0].skills = vec![SkillBox::new(TestSkill::attack())];
+        args.enemy.default_passive = vec![PassiveBox::new(TestPassive::hook())];
+        let mut core = GameCoreActor::new(args).unwrap();
+
+        let mut output_buffer = Vec::new();
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+        assert!(core.state().get_enemy().lt().passive.contains(TIMED.id));
+
+        let skill_id = core.state().get_chars()[0].get_skills()[0].runtime_id();
+        core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+            .unwrap();
+        core.state().get_chars()[0].lt().mp()
+    };
+
+    assert!((mp_after_skill(true) - mp_after_skill(false) - 5.0).abs() < 0.001);
+}
+
+#[test]
+fn redirected_damage_skips_original_target_modifiers() {
+    let redirect = TestPassive {
+        modify_recv_damage: |dmg| dmg.redirect(LtId::Enemy),
+        damage_modify_order: -1,
+        ..TestPassive::new(&MODIFY_DAMAGE)
+    };
+    let halve = TestPassive {
+        modify_recv_damage: |dmg| dmg.mul(0.5),
+        ..TestPassive::new(&MODIFY_DAMAGE2)
+    };
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    skill.actions = vec![
+        EnemySkillActionGroup::new(
+            0,
+            vec![
+                (
+                    EnemySkillTarget::AllChar,
+                    EnemySkillAction::AddPassive(PassiveBox::new(redirect)),
+                ),
+                (
+                    EnemySkillTarget::AllChar,
+                    EnemySkillAction::AddPassive(PassiveBox::new(halve)),
+                ),
+            ],
+        ),
+        EnemySkillActionGroup::new(
+            1,
+            vec![(
+                EnemySkillTarget::AllChar,
+                EnemySkillAction::Damage {
+                    ty: DamageType::Fixed,
+                    dmg_mag: 8.0,
+                    count: 1,
+                },
+            )],
+        ),
+    ];
+    let mut core = GameCoreActor::new(enemy_args(vec![skill], vec![vec![1]])).unwrap();
+    let enemy_before = core.state().get_enemy().lt().hp();
+    let char_before = core.state().get_chars()[0].lt().hp();
+
+    tick_frames(&mut core, 2);
+
+    // 敵に移ったダメージはキャラクターの半減を受けない
+    let enemy_loss = enemy_before - core.state().get_enemy().lt().hp();
+    assert!((enemy_loss - 8.0).abs() < 0.001);
+    assert_eq!(core.state().get_chars()[0].lt().hp(), char_before);
+}
+
+#[test]
+fn damage_modified_before_application() {
+    let enemy_hp_loss = |passives: Vec<TestPassive>| {
+        let mut args = enemy_args(vec![enemy_skill1()], vec![vec![1]]);
+        args.chars[0].skills = vec![SkillBox::new(TestSkill::attack())];
+        args.enemy.default_passive = passives.into_iter().map(PassiveBox::new).collect();
+        let mut core = GameCoreActor::new(args).unwrap();
+        let before = core.state().get_enemy().lt().hp();
+
+        let skill_id = core.state().get_chars()[0].get_skills()[0].runtime_id();
+        let mut output_buffer = Vec::new();
+        core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+            .unwrap();
+        for _ in 0..2 {
+            core.tick(UserInput::None, &mut output_buffer).unwrap();
+        }
+        before - core.state().get_enemy().lt().hp()
+    };
+
+    let halve = TestPassive {
+        modify_recv_damage: |dmg| dmg.mul(0.5),
+        ..TestPassive::new(&MODIFY_DAMAGE)
+    };
+    let cap = TestPassive {
+        modify_recv_damage: |dmg| dmg.cap(1.0),
+        damage_modify_order: -1,
+        ..TestPassive::new(&MODIFY_DAMAGE2)
+    };
+    assert!(enemy_hp_loss(vec![]) > 2.0);
+    // orderが小さい上限が先に適用される
+    assert!((enemy_hp_loss(vec![halve.clone(), cap.clone()]) - 0.5).abs() < 0.001);
+
+    let nullify = TestPassive {
+        modify_recv_damage: |dmg| dmg.nullify(),
+        ..TestPassive::new(&MODIFY_DAMAGE2)
+    };
+    assert_eq!(enemy_hp_loss(vec![halve, nullify]), 0.0);
+}
+
+#[test]
+fn passive_status_channels() {
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    skill.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::AllChar,
+            EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::status(|s| {
+                s.speed_mag_buff.add(1.0);
+                s.mp_efficiency_buff.add(1.0);
+                s.max_mp_mag_debuff.mul(0.5);
+                s.recv_heal_mag_debuff.mul(0.5);
+            }))),
+        )],
+    )];
+    let mut core = GameCoreActor::new(enemy_args(vec![skill], vec![vec![1]])).unwrap();
+    let lt = core.state().get_chars()[0].lt();
+    let (speed, max_mp) = (lt.speed(), lt.max_mp());
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+
+    let state = core.state();
+    let char = &state.get_chars()[0];
+    assert!((char.lt().speed() - speed * 2.0).abs() < 0.001);
+    assert!((char.lt().max_mp() - max_mp * 0.5).abs() < 0.001);
+    assert!((char.get_skills()[0].cost(state).need_mp() - 5.0).abs() < 0.001);
+
+    let Effect::HealHp { num, .. } = Effect::new_heal_hp(state, None, char.lt_id(), 10.0) else {
+        unreachable!()
+    };
+    assert!((num - 5.0).abs() < 0.001);
+}
+
+#[test]
+fn dispel_passives() {
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    skill.recovery_frame = 100;
+    skill.actions = vec![
+        EnemySkillActionGroup::new(
+            0,
+            vec![
+                (
+                    EnemySkillTarget::AllChar,
+                    EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::taunt())),
+                ),
+                (
+                    EnemySkillTarget::AllChar,
+                    EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::timed(1000))),
+                ),
+                (
+                    EnemySkillTarget::AllChar,
+                    EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::hook())),
+                ),
+            ],
+        ),
+        EnemySkillActionGroup::new(
+            1,
+            vec![(
+                EnemySkillTarget::AllChar,
+                EnemySkillAction::DispelPassives {
+                    filter: PassiveFilter::Category(PassiveCategory::Buff),
+                    num: 10,
+                },
+            )],
+        ),
+        EnemySkillActionGroup::new(
+            2,
+            vec![(
+                EnemySkillTarget::AllChar,
+                EnemySkillAction::DispelPassives {
+                    filter: PassiveFilter::Tag("timed"),
+                    num: 1,
+                },
+            )],
+        ),
+    ];
+    let mut core = GameCoreActor::new(enemy_args(vec![skill], vec![vec![1]])).unwrap();
+    let passive = |core: &GameCoreActor| core.state().get_chars()[0].lt().passive.clone();
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    assert!(passive(&core).contains(TAUNT.id) && passive(&core).contains(TIMED.id));
+
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    assert!(!passive(&core).contains(TAUNT.id) && passive(&core).contains(TIMED.id));
+
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    assert!(!passive(&core).contains(TIMED.id));
+    // Neutralは解除されない
+    assert!(passive(&core).contains(HOOK.id));
+}
+
+#[test]
+fn passive_immunity() {
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    skill.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![
+            (
+                EnemySkillTarget::Self_,
+                EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::timed(100))),
+            ),
+            (
+                EnemySkillTarget::Self_,
+                EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::taunt())),
+            ),
+        ],
+    )];
+    let mut args = enemy_args(vec![skill], vec![vec![1]]);
+    args.enemy.resistances = vec![("timed", 1.0), ("taunt", 0.0)];
+    let mut core = GameCoreActor::new(args).unwrap();
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+
+    let passive = &core.state().get_enemy().lt().passive;
+    assert!(!passive.contains(TIMED.id));
+    assert!(passive.contains(TAUNT.id));
+    assert!(output_buffer.iter().any(|o| matches!(
+        o,
+        GameCoreOutput::Event(OutputEvent::PassiveImmune {
+            target_id: LtId::Enemy,
+            passive_id
+        }) if *passive_id == TIMED.id
+    )));
+}
+
+#[test]
+fn crowd_control() {
+    let enemy_progress_after_10_frames = |status: fn(&mut PassiveStatus)| {
+        let mut skill = enemy_skill1();
+        skill.start_up_frames = 100;
+        let mut args = enemy_args(vec![skill], vec![vec![1]]);
+        args.enemy.default_passive = vec![PassiveBox::new(TestPassive::status(status))];
+        let mut core = GameCoreActor::new(args).unwrap();
+        let mut output_buffer = Vec::new();
+        for _ in 0..10 {
+            core.tick(UserInput::None, &mut output_buffer).unwrap();
+        }
+        core.state()
+            .get_enemy()
+            .current_condition()
+            .progress
+            .current()
+    };
+
+    assert_eq!(enemy_progress_after_10_frames(|_| {}), 10.0);
+    assert_eq!(enemy_progress_after_10_frames(|s| s.stun = true), 0.0);
+    assert_eq!(enemy_progress_after_10_frames(|s| s.slow.mul(0.5)), 5.0);
+
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    skill.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::AllChar,
+            EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::status(|s| s.stun = true))),
+        )],
+    )];
+    let mut args = enemy_args(vec![skill], vec![vec![1]]);
+    args.chars[0].skills = vec![SkillBox::new(TestSkill::attack())];
+    let mut core = GameCoreActor::new(args).unwrap();
+    let skill_id = core.state().get_chars()[0].get_skills()[0].runtime_id();
+    assert!(core.state().get_skill(skill_id).useable(core.state()));
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    assert!(!core.state().get_skill(skill_id).useable(core.state()));
+    assert!(
+        core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+            .is_err()
+    );
+}
+
+#[test]
+fn silence_blocks_only_magic_skills() {
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    skill.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::AllChar,
+            EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::status(|s| {
+                s.silence = true
+            }))),
+        )],
+    )];
+    let mut args = enemy_args(vec![skill], vec![vec![1]]);
+    args.chars[0].skills = vec![
+        SkillBox::new(TestSkill {
+            is_magic: true,
+            ..TestSkill::instant()
+        }),
+        SkillBox::new(TestSkill::attack()),
+    ];
+    let mut core = GameCoreActor::new(args).unwrap();
+    let skills = core.state().get_chars()[0].get_skills();
+    let magic_id = skills[0].runtime_id();
+    let physics_id = skills[1].runtime_id();
+    assert!(core.state().get_skill(magic_id).useable(core.state()));
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    assert!(core.state().get_chars()[0].lt().is_silenced());
+    assert!(!core.state().get_skill(magic_id).useable(core.state()));
+    assert!(
+        core.tick(
+            UserInput::UseSkill { skill_id: magic_id },
+            &mut output_buffer
+        )
+        .is_err()
+    );
+
+    // 魔法でないスキルは沈黙中でも使用できる
+    assert!(core.state().get_skill(physics_id).useable(core.state()));
+    core.tick(
+        UserInput::UseSkill {
+            skill_id: physics_id,
+        },
+        &mut output_buffer,
+    )
+    .unwrap();
+}
+
+const POISON: PassiveInfomation = PassiveInfomation {
+    id: 10,
+    name: "毒",
+    icon_key: "",
+    description: "",
+    category: PassiveCategory::Debuff,
+    tags: &["poison"],
+};
+
+const THORNS: PassiveInfomation = PassiveInfomation {
+    id: 11,
+    name: "反射",
+    icon_key: "",
+    description: "",
+    category: PassiveCategory::Buff,
+    tags: &[],
+};
+
+#[test]
+fn library_damage_over_time() {
+    let mut args = enemy_args(vec![enemy_skill1()], vec![vec![1]]);
+    args.enemy.default_passive = vec![PassiveBox::new(library::DamageOverTime {
+        info: &POISON,
+        duration: Some(15),
+        stack_policy: PassiveStackPolicy::RefreshDuration,
+        interval: 10,
+        dmg: 5.0,
+        causer: None,
+    })];
+    let mut core = GameCoreActor::new(args).unwrap();
+    let before = core.state().get_enemy().lt().hp();
+
+    let mut output_buffer = Vec::new();
+    for _ in 0..30 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+    // 0フレーム目と10フレーム目の2回
+    let enemy = core.state().get_enemy().lt();
+    assert!((before - enemy.hp() - 10.0).abs() < 0.001);
+    assert!(!enemy.passive.contains(POISON.id));
+}
+
+#[test]
+fn library_damage_over_time_stacks() {
+    let poison = PassiveBox::new(library::DamageOverTime {
+        info: &POISON,
+        duration: None,
+        stack_policy: PassiveStackPolicy::Stack { max: 3 },
+        interval: 10,
+        dmg: 5.0,
+        causer: None,
+    });
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    skill.recovery_frame = 100;
+    skill.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![
+            (
+                EnemySkillTarget::Self_,
+                EnemySkillAction::AddPassive(poison.clone()),
+            ),
+            (
+                EnemySkillTarget::Self_,
+                EnemySkillAction::AddPassive(poison.clone()),
+            ),
+        ],
+    )];
+    let mut args = enemy_args(vec![skill], vec![vec![1]]);
+    args.enemy.default_passive = vec![poison];
+    let mut core = GameCoreActor::new(args).unwrap();
+    let before = core.state().get_enemy().lt().hp();
+
+    // 0フレーム目は1スタック、10フレーム目は3スタック分のダメージ
+    tick_frames(&mut core, 11);
+    let enemy = core.state().get_enemy().lt();
+    assert_eq!(enemy.passive.stacks(POISON.id), 3);
+    assert!((before - enemy.hp() - 20.0).abs() < 0.001);
+}
+
+#[test]
+fn library_damage_over_time_interval_from_application() {
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 3;
+    skill.recovery_frame = 100;
+    skill.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::Self_,
+            EnemySkillAction::AddPassive(PassiveBox::new(library::DamageOverTime {
+                info: &POISON,
+                duration: None,
+                stack_policy: PassiveStackPolicy::RefreshDuration,
+                interval: 10,
+                dmg: 5.0,
+                causer: None,
+            })),
+        )],
+    )];
+    let mut core = GameCoreActor::new(enemy_args(vec![skill], vec![vec![1]])).unwrap();
+    let before = core.state().get_enemy().lt().hp();
+    let hp_loss = |core: &GameCoreActor| before - core.state().get_enemy().lt().hp();
+
+    // 4回目のtickで付与され、次のフレームから10フレームごとにダメージを受ける
+    tick_frames(&mut core, 5);
+    assert!((hp_loss(&core) - 5.0).abs() < 0.001);
+
+    tick_frames(&mut core, 9);
+    assert!((hp_loss(&core) - 5.0).abs() < 0.001);
+
+    tick_frames(&mut core, 1);
+    assert!((hp_loss(&core) - 10.0).abs() < 0.001);
+}
+
+#[test]
+fn library_thorns() {
+    let mut args = enemy_args(vec![enemy_skill1()], vec![vec![1]]);
+    args.chars[0].skills = vec![SkillBox::new(TestSkill::attack())];
+    args.enemy.default_passive = vec![PassiveBox::new(library::Thorns {
+        info: &THORNS,
+        duration: None,
+        stack_policy: PassiveStackPolicy::Replace,
+        per: 0.5,
+    })];
+    let mut core = GameCoreActor::new(args).unwrap();
+    let enemy_before = core.state().get_enemy().lt().hp();
+    let char_before = core.state().get_chars()[0].lt().hp();
+
+    let skill_id = core.state().get_chars()[0].get_skills()[0].runtime_id();
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+        .unwrap();
+    for _ in 0..2 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+
+    let enemy_loss = enemy_before - core.state().get_enemy().lt().hp();
+    let char_loss = char_before - core.state().get_chars()[0].lt().hp();
+    assert!(enemy_loss > 0.0);
+    assert!((char_loss - enemy_loss * 0.5).abs() < 0.001);
+}
+
+const GUARD: PassiveInfomation = PassiveInfomation {
+    id: 14,
+    name: "守り",
+    icon_key: "",
+    description: "",
+    category: PassiveCategory::Buff,
+    tags: &[],
+};
+
+#[test]
+fn library_damage_reduction_invalid_mag() {
+    let policy = PassiveStackPolicy::Replace;
+    assert!(library::DamageReduction::new(&GUARD, None, policy, 1.5, None).is_err());
+    assert!(library::DamageReduction::new(&GUARD, None, policy, -0.1, None).is_err());
+    assert!(library::DamageReduction::new(&GUARD, None, policy, 0.0, None).is_ok());
+}
+
+#[test]
+fn library_damage_reduction_stacks() {
+    let guard = PassiveBox::new(
+        library::DamageReduction::new(
+            &GUARD,
+            None,
+            PassiveStackPolicy::Stack { max: 3 },
+            0.5,
+            None,
+        )
+        .unwrap(),
+    );
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    skill.actions = vec![
+        EnemySkillActionGroup::new(
+            0,
+            vec![
+                (
+                    EnemySkillTarget::Self_,
+                    EnemySkillAction::AddPassive(guard.clone()),
+                ),
+                (
+                    EnemySkillTarget::Self_,
+                    EnemySkillAction::AddPassive(guard.clone()),
+                ),
+            ],
+        ),
+        EnemySkillActionGroup::new(
+            1,
+            vec![(
+                EnemySkillTarget::Self_,
+                EnemySkillAction::Damage {
+                    ty: DamageType::Fixed,
+                    dmg_mag: 8.0,
+                    count: 1,
+                },
+            )],
+        ),
+    ];
+    let mut args = enemy_args(vec![skill], vec![vec![1]]);
+    args.enemy.default_passive = vec![guard];
+    let mut core = GameCoreActor::new(args).unwrap();
+    let before = core.state().get_enemy().lt().hp();
+
+    let mut output_buffer = Vec::new();
+    for _ in 0..2 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+
+    let enemy = core.state().get_enemy().lt();
+    assert_eq!(enemy.passive.stacks(GUARD.id), 3);
+    // 0.5が3回掛けられる
+    assert!((before - enemy.hp() - 1.0).abs() < 0.001);
+}
+
+const BERSERK: PassiveInfomation = PassiveInfomation {
+    id: 12,
+    name: "狂戦士",
+    icon_key: "",
+    description: "",
+    category: PassiveCategory::Buff,
+    tags: &[],
+};
+
+#[test]
+fn library_conditional_status() {
+    let max_hp = GameCoreActor::new(enemy_args(vec![enemy_skill1()], vec![vec![1]]))
+        .unwrap()
+        .state()
+        .get_enemy()
+        .lt()
+        .max_hp();
+
+    let mut args = enemy_args(vec![enemy_skill1()], vec![vec![1]]);
+
+    args.enemy.default_passive = vec![
+        PassiveBox::new(library::ConditionalStatusModifier {
+            info: &BERSERK,
+            duration: None,
+            stack_policy: PassiveStackPolicy::Replace,
+            count: |owner, state| (state.get_lt(owner).hp_per() < 0.5) as u32,
+            modify: |s| s.physics_attuck_mag_buff.add(0.3),
+        }),
+        PassiveBox::new(library::DamageOverTime {
+            info: &POISON,
+            duration: None,
+            stack_policy: PassiveStackPolicy::Replace,
+            interval: 1000,
+            dmg: max_hp * 0.6,
+            causer: None,
+        }),
+    ];
+    let mut core = GameCoreActor::new(args).unwrap();
+    let before = core.state().get_enemy().lt().physics_attuck();
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    // ダメージを受けたフレームではまだ反映されない
+    assert!(core.state().get_enemy().lt().hp_per() < 0.5);
+    assert!((core.state().get_enemy().lt().physics_attuck() - before).abs() < 0.001);
+
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    let after = core.state().get_enemy().lt().physics_attuck();
+    assert!((after - before * 1.3).abs() < 0.001);
+}
+
+const AURA: PassiveInfomation = PassiveInfomation {
+    id: 13,
+    name: "威圧",
+    icon_key: "",
+    description: "",
+    category: PassiveCategory::Debuff,
+    tags: &[],
+};
+
+#[test]
+fn library_aura() {
+    let mut args = enemy_args(vec![enemy_skill1()], vec![vec![1]]);
+    args.chars = vec![char1(), char1()];
+    args.enemy.default_passive = vec![PassiveBox::new(library::Aura {
+        info: &AURA,
+        duration: Some(5),
+        stack_policy: PassiveStackPolicy::Replace,
+        target: AuraTarget::Enemies,
+        modify: |s| s.physics_attuck_mag_debuff.mul(0.5),
+    })];
+    let mut core = GameCoreActor::new(args).unwrap();
+    let char_before = core.state().get_chars()[1].lt().physics_attuck();
+    let enemy_before = core.state().get_enemy().lt().physics_attuck();
+
+    let mut output_buffer = Vec::new();
+    for _ in 0..2 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+    for c in core.state().get_chars() {
+        assert!((c.lt().physics_attuck() - char_before * 0.5).abs() < 0.001);
+    }
+    assert!((core.state().get_enemy().lt().physics_attuck() - enemy_before).abs() < 0.001);
+
+    // 効果時間が切れると取り除かれる
+    for _ in 0..5 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+    assert!(!core.state().get_enemy().lt().passive.contains(AURA.id));
+    for c in core.state().get_chars() {
+        assert!((c.lt().physics_attuck() - char_before).abs() < 0.001);
+    }
+}
+
+#[test]
+fn library_status_summary() {
+    let status = library::StatusModifier {
+        info: &BERSERK,
+        duration: None,
+        stack_policy: PassiveStackPolicy::Replace,
+        modify: |s| {
+            s.physics_attuck_mag_buff.add(0.3);
+            s.add_str += 5.0;
+        },
+    };
+    assert_eq!(status.summary(), "物理攻撃力+30% STR+5");
+
+    let aura = library::Aura {
+        info: &AURA,
+        duration: None,
+        stack_policy: PassiveStackPolicy::Replace,
+        target: AuraTarget::Enemies,
+        modify: |s| s.physics_attuck_mag_debuff.mul(0.5),
+    };
+    assert_eq!(aura.summary(), "敵全体 物理攻撃力-50%");
+
+    let taunt = library::Taunt {
+        info: &TAUNT,
+        duration: None,
+        stack_policy: PassiveStackPolicy::Replace,
+    };
+    assert_eq!(taunt.summary(), "挑発");
+}
+
+fn cost_skill_core(skill: TestSkill) -> GameCoreActor {
+    let mut args = enemy_args(vec![enemy_skill1()], vec![vec![1]]);
+    args.chars[0].skills = vec![SkillBox::new(skill)];
+    GameCoreActor::new(args).unwrap()
+}
+
+#[test]
+fn skill_hp_cost() {
+    let mut core = cost_skill_core(TestSkill::instant());
+    let max_hp = core.state().get_chars()[0].lt().max_hp();
+    core = cost_skill_core(TestSkill {
+        need_hp: max_hp * 0.6,
+        max_charges: 2,
+        ..TestSkill::instant()
+    });
+
+    let skill_id = core.state().get_chars()[0].get_skills()[0].runtime_id();
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+        .unwrap();
+    let char = core.state().get_chars()[0].lt();
+    assert!((char.hp() - max_hp * 0.4).abs() < 0.001);
+
+    // 残りHPが消費HP以下なので使用できない
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    assert!(!core.state().get_skill(skill_id).useable(core.state()));
+    assert!(
+        core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+            .is_err()
+    );
+}
+
+#[test]
+fn skill_resource_cost() {
+    let core = cost_skill_core(TestSkill {
+        need_resource: Some(("rage", 10.0)),
+        ..TestSkill::instant()
+    });
+    let skill_id = core.state().get_chars()[0].get_skills()[0].runtime_id();
+    assert_eq!(core.state().get_chars()[0].lt().resource("rage"), 0.0);
+    assert!(!core.state().get_skill(skill_id).useable(core.state()));
+}
+
+#[test]
+fn zero_mp_efficiency() {
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    skill.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::AllChar,
+            EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::status(|s| {
+                s.mp_efficiency_debuff.mul(0.0)
+            }))),
+        )],
+    )];
+    let mut args = enemy_args(vec![skill], vec![vec![1]]);
+    args.chars[0].skills = vec![
+        SkillBox::new(TestSkill::instant()),
+        SkillBox::new(TestSkill::stay()),
+    ];
+    let mut core = GameCoreActor::new(args).unwrap();
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    assert!(core.state().get_chars()[0].lt().passive.contains(STATUS.id));
+
+    // 消費MPが0のスキルだけが使える
+    let skills = core.state().get_chars()[0].get_skills();
+    assert!(skills[0].useable(core.state()));
+    assert_eq!(skills[0].cost(core.state()).need_mp(), 0.0);
+    assert!(!skills[1].useable(core.state()));
+}
+
+#[test]
+fn skill_charges() {
+    let mut core = cost_skill_core(TestSkill {
+        max_charges: 2,
+        ..TestSkill::instant()
+    });
+    let skill_id = core.state().get_chars()[0].get_skills()[0].runtime_id();
+    let mut output_buffer = Vec::new();
+
+    core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+        .unwrap();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    assert_eq!(core.state().get_skill(skill_id).charges(), 1);
+
+    core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+        .unwrap();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    assert_eq!(core.state().get_skill(skill_id).charges(), 0);
+    assert!(
+        core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+            .is_err()
+    );
+
+    // それぞれ個別に回復する
+    for _ in 0..97 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+    assert_eq!(core.state().get_skill(skill_id).charges(), 1);
+    for _ in 0..2 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+    assert_eq!(core.state().get_skill(skill_id).charges(), 2);
+}
+
+#[test]
+fn weapon_resources() {
+    let mut args = enemy_args(vec![enemy_skill1()], vec![vec![1]]);
+    let mut hammer = char1();
+    hammer.weapon.ty = WeaponType::Hammer;
+    hammer.skills = vec![SkillBox::new(TestSkill::attack())];
+    let mut bow = char1();
+    bow.weapon.ty = WeaponType::Bow;
+    args.chars = vec![hammer, bow];
+    let mut core = GameCoreActor::new(args).unwrap();
+
+    assert_eq!(
+        core.state().get_chars()[0].lt().resource(resource::RAGE),
+        0.0
+    );
+    assert_eq!(
+        core.state().get_chars()[1].lt().resource(resource::RAGE),
+        0.0
+    );
+
+    let skill_id = core.state().get_chars()[0].get_skills()[0].runtime_id();
+    let enemy_before = core.state().get_enemy().lt().hp();
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+        .unwrap();
+    for _ in 0..9 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+
+    // 与えたダメージに応じて溜まる
+    let dealt = enemy_before - core.state().get_enemy().lt().hp();
+    let rage = core.state().get_chars()[0].lt().resource(resource::RAGE);
+    assert!(dealt > 0.0);
+    assert!((rage - (dealt * 0.5).min(100.0)).abs() < 0.001);
+
+    // 10フレームで0.5秒分
+    let focus = core.state().get_chars()[1].lt().resource(resource::FOCUS);
+    assert!((focus - 0.5).abs() < 0.001);
+    assert_eq!(
+        core.state().get_chars()[1]
+            .lt()
+            .resources()
+            .map(|(bar, _)| bar.key)
+            .collect::<Vec<_>>(),
+        vec![resource::FOCUS]
+    );
+}
+
+#[test]
+fn custom_resource_consumed_by_skill() {
+    let mut args = enemy_args(vec![enemy_skill1()], vec![vec![1]]);
+    args.chars[0].resources = vec![ResourceBar {
+        key: "mana_crystal",
+        name: "",
+        max: 20.0,
+        initial: 15.0,
+        generation: vec![],
+    }];
+    args.chars[0].skills = vec![SkillBox::new(TestSkill {
+        need_resource: Some(("mana_crystal", 10.0)),
+        ..TestSkill::instant()
+    })];
+    let mut core = GameCoreActor::new(args).unwrap();
+    let skill_id = core.state().get_chars()[0].get_skills()[0].runtime_id();
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+        .unwrap();
+    assert_eq!(
+        core.state().get_chars()[0].lt().resource("mana_crystal"),
+        5.0
+    );
+}
+
+#[test]
+fn duplicate_resource_is_error() {
+    let mut args = args();
+    args.chars[0].weapon.ty = WeaponType::Hammer;
+    args.chars[0].resources = vec![ResourceBar::rage()];
+    assert!(GameCoreActor::new(args).is_err());
+}
+
+/// 10フレームごとに1ダメージを受ける状態で詠唱する
+fn chant_core(interruption: ChantInterruption, uninterruptible: bool) -> GameCoreActor {
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    let mut actions = vec![(
+        EnemySkillTarget::AllChar,
+        EnemySkillAction::AddPassive(PassiveBox::new(library::DamageOverTime {
+            info: &POISON,
+            duration: None,
+            stack_policy: PassiveStackPolicy::Replace,
+            interval: 10,
+            dmg: 1.0,
+            causer: None,
+        })),
+    )];
+    if uninterruptible {
+        actions.push((
+            EnemySkillTarget::AllChar,
+            EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::status(|s| {
+                s.uninterruptible = true
+            }))),
+        ));
+    }
+    skill.actions = vec![EnemySkillActionGroup::new(0, actions)];
+
+    let mut args = enemy_args(vec![skill], vec![vec![1]]);
+    args.chars[0].skills = vec![SkillBox::new(TestSkill {
+        interruption,
+        ..TestSkill::chant()
+    })];
+    let mut core = GameCoreActor::new(args).unwrap();
+
+    let skill_id = core.state().get_chars()[0].get_skills()[0].runtime_id();
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+        .unwrap();
+    core
+}
+
+fn chant_progress(core: &GameCoreActor) -> Option<f32> {
+    match core.state().get_chars()[0].current_condition() {
+        CharCondition::UseSkill(p) => Some(p.overall_progress.progress()),
+        CharCondition::Wait => None,
+    }
+}
+
+#[test]
+fn chant_pushback() {
+    let mut normal = chant_core(ChantInterruption::default(), false);
+    let mut pushed = chant_core(
+        ChantInterruption {
+            pushback_ms: 50.0,
+            interrupt_threshold: None,
+        },
+        false,
+    );
+    let mut uninterruptible = chant_core(
+        ChantInterruption {
+            pushback_ms: 50.0,
+            interrupt_threshold: None,
+        },
+        true,
+    );
+
+    let mut output_buffer = Vec::new();
+    for _ in 0..30 {
+        normal.tick(UserInput::None, &mut output_buffer).unwrap();
+        pushed.tick(UserInput::None, &mut output_buffer).unwrap();
+        uninterruptible
+            .tick(UserInput::None, &mut output_buffer)
+            .unwrap();
+    }
+
+    let normal = chant_progress(&normal).unwrap();
+    assert!(chant_progress(&pushed).unwrap() < normal);
+    assert!((chant_progress(&uninterruptible).unwrap() - normal).abs() < 0.0001);
+}
+
+#[test]
+fn chant_interrupt() {
+    let interruption = ChantInterruption {
+        pushback_ms: 0.0,
+        interrupt_threshold: Some(0.0),
+    };
+    let mut core = chant_core(interruption, false);
+    let mut protected = chant_core(interruption, true);
+
+    let mut output_buffer = Vec::new();
+    let mut protected_output_buffer = Vec::new();
+    let mut interrupted = 0;
+    for _ in 0..30 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+        for output in output_buffer.drain(..) {
+            if let GameCoreOutput::Event(OutputEvent::SkillInterrupted { .. }) = output {
+                interrupted += 1;
+            }
+        }
+        protected
+            .tick(UserInput::None, &mut protected_output_buffer)
+            .unwrap();
+    }
+
+    assert_eq!(interrupted, 1);
+    assert!(!protected_output_buffer.iter().any(|o| matches!(
+        o,
+        GameCoreOutput::Event(OutputEvent::SkillInterrupted { .. })
+    )));
+    assert!(chant_progress(&core).is_none());
+    assert!(chant_progress(&protected).is_some());
+}
+
+#[test]
+fn chant_and_action_speed() {
+    let core = GameCoreActor::new(args()).unwrap();
+    let lt = core.state().get_chars()[0].lt();
+    assert_eq!(lt.chant_speed(), lt.speed());
+    assert_eq!(lt.action_speed(), lt.speed());
+
+    // 詠唱だけ2倍の速さになる
+    let mut skill = enemy_skill1();
+    skill.start_up_frames = 0;
+    skill.actions = vec![EnemySkillActionGroup::new(
+        0,
+        vec![(
+            EnemySkillTarget::AllChar,
+            EnemySkillAction::AddPassive(PassiveBox::new(TestPassive::status(|s| {
+                s.chant_speed_mag_buff.add(1.0);
+                s.action_speed_mag_debuff.mul(0.5);
+            }))),
+        )],
+    )];
+    let mut args = enemy_args(vec![skill], vec![vec![1]]);
+    args.chars[0].skills = vec![SkillBox::new(TestSkill::chant())];
+    let mut core = GameCoreActor::new(args).unwrap();
+
+    let mut output_buffer = Vec::new();
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    let lt = core.state().get_chars()[0].lt();
+    let speed = lt.speed();
+    assert!((lt.chant_speed() - speed * 2.0).abs() < 0.001);
+    assert!((lt.action_speed() - speed * 0.5).abs() < 0.001);
+
+    let skill_id = core.state().get_chars()[0].get_skills()[0].runtime_id();
+    // 使用したフレームはまだ進まない
+    core.tick(UserInput::UseSkill { skill_id }, &mut output_buffer)
+        .unwrap();
+    for _ in 0..9 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+    }
+    let CharCondition::UseSkill(progress) = core.state().get_chars()[0].current_condition() else {
+        panic!("詠唱中のはず");
+    };
+    assert_eq!(progress.kind, CharSkillProgressKind::Chanting);
+    assert!((progress.chunk_progress.progress() - speed * 2.0 * 9.0 / 1000.0).abs() < 0.001);
+
+    // 詠唱を終えて行動に入るまで進める
+    let acting_progress =
+        |core: &GameCoreActor| match core.state().get_chars()[0].current_condition() {
+            CharCondition::UseSkill(p) if p.kind == CharSkillProgressKind::Acting => {
+                Some(p.chunk_progress.progress())
+            }
+            _ => None,
+        };
+    let mut before = None;
+    for _ in 0..1000 {
+        core.tick(UserInput::None, &mut output_buffer).unwrap();
+        before = acting_progress(&core);
+        if before.is_some() {
+            break;
+        }
+    }
+    let before = before.expect("行動に入っているはず");
+
+    // 行動は0.5倍の速さで進む
+    core.tick(UserInput::None, &mut output_buffer).unwrap();
+    let after = acting_progress(&core).expect("100msの行動はまだ終わらない");
+    assert!((after - before - speed * 0.5 / 100.0).abs() < 0.001);
+}